@@ -3,24 +3,192 @@ hc_agent provides a library for managing holochain agent info, including identit
 
 */
 
+extern crate ed25519_dalek;
+extern crate hc_dna;
+extern crate rand;
+extern crate rust_base58;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+extern crate sha2;
+
+use ed25519_dalek::{ExpandedSecretKey, PublicKey, SecretKey, Signature, Verifier};
+use hc_dna::hash;
+use rust_base58::{FromBase58, ToBase58};
+use sha2::{Digest, Sha256};
+use std::fmt;
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct Identity {
     content: String,
 }
+
+/// An ed25519 signing key pair
+#[derive(Clone, PartialEq)]
+struct KeyPair {
+    public: [u8; 32],
+    /// sensitive! never log or share the secret key
+    secret: [u8; 32],
+}
+
+impl fmt::Debug for KeyPair {
+    // keep the secret key out of debug output
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "KeyPair {{ public: {} }}", self.public.to_base58())
+    }
+}
+
+/// how an agent is serialized, with its keys base58 encoded
+#[derive(Serialize, Deserialize)]
+struct AgentJson {
+    identity: String,
+    #[serde(default)]
+    public_key: Option<String>,
+    /// sensitive! only ever written to places as private as the agent's keys themselves
+    #[serde(default)]
+    secret_key: Option<String>,
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct Agent {
     identity: Identity,
+    keys: Option<KeyPair>,
 }
 
 impl Agent {
     pub fn new(id: Identity) -> Self {
-        Agent { identity: id }
+        Agent {
+            identity: id,
+            keys: None,
+        }
     }
     pub fn from_string(text: &str) -> Self {
         Agent::new(Identity {
             content: text.to_string(),
         })
     }
+
+    /// create an agent with a newly generated ed25519 key pair,
+    /// identified by the base58 encoding of its public key
+    pub fn generate() -> Self {
+        Agent::from_secret(SecretKey::generate(&mut rand::rngs::OsRng))
+    }
+
+    /// create an agent whose key pair is derived from seed, so that the same seed
+    /// always gives the same agent; only use this for tests and fixtures
+    pub fn from_seed(seed: &[u8]) -> Self {
+        // a sha2-256 digest is exactly the 32 bytes a secret key needs
+        let digest = Sha256::digest(seed);
+        Agent::from_secret(SecretKey::from_bytes(&digest).unwrap())
+    }
+
+    fn from_secret(secret: SecretKey) -> Self {
+        let public = PublicKey::from(&secret);
+        Agent {
+            identity: Identity {
+                content: public.as_bytes().to_base58(),
+            },
+            keys: Some(KeyPair {
+                public: public.to_bytes(),
+                secret: secret.to_bytes(),
+            }),
+        }
+    }
+
+    /// the content address of the agent's identity
+    pub fn address(&self) -> String {
+        hash::address(&self.identity.content)
+    }
+
+    /// serialize the agent, including its secret key if it has a key pair,
+    /// so the json is as sensitive as the secret key itself
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(&AgentJson {
+            identity: self.identity.content.clone(),
+            public_key: self.keys.as_ref().map(|keys| keys.public.to_base58()),
+            secret_key: self.keys.as_ref().map(|keys| keys.secret.to_base58()),
+        })
+    }
+
+    /// deserialize an agent serialized with `to_json`, failing if its keys don't make a pair
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        let json: AgentJson = serde_json::from_str(json)?;
+        let keys = match (json.public_key, json.secret_key) {
+            (None, None) => None,
+            (Some(public), Some(secret)) => {
+                let secret = decode_key(&secret)
+                    .and_then(|secret| SecretKey::from_bytes(&secret).ok())
+                    .ok_or_else(|| invalid("invalid secret key"))?;
+                let public_key = PublicKey::from(&secret);
+                if decode_key(&public) != Some(public_key.to_bytes().to_vec()) {
+                    return Err(invalid("public key doesn't match secret key"));
+                }
+                Some(KeyPair {
+                    public: public_key.to_bytes(),
+                    secret: secret.to_bytes(),
+                })
+            }
+            _ => return Err(invalid("public and secret keys must be given together")),
+        };
+        Ok(Agent {
+            identity: Identity {
+                content: json.identity,
+            },
+            keys,
+        })
+    }
+
+    /// the agent's public key, if it has a key pair
+    pub fn public_key(&self) -> Option<Vec<u8>> {
+        self.keys.as_ref().map(|keys| keys.public.to_vec())
+    }
+
+    /// sign data with the agent's secret key, if it has a key pair
+    pub fn sign(&self, data: &[u8]) -> Option<Vec<u8>> {
+        self.keys.as_ref().map(|keys| {
+            // the stored keys were produced by ed25519_dalek so are always valid
+            let secret = SecretKey::from_bytes(&keys.secret).unwrap();
+            let public = PublicKey::from_bytes(&keys.public).unwrap();
+            ExpandedSecretKey::from(&secret)
+                .sign(data, &public)
+                .to_bytes()
+                .to_vec()
+        })
+    }
+
+    /// check that a signature over data was made with the secret key matching pubkey
+    pub fn verify(pubkey: &[u8], data: &[u8], sig: &[u8]) -> bool {
+        let public = match PublicKey::from_bytes(pubkey) {
+            Ok(public) => public,
+            Err(_) => return false,
+        };
+        let signature = match Signature::from_bytes(sig) {
+            Ok(signature) => signature,
+            Err(_) => return false,
+        };
+        public.verify(data, &signature).is_ok()
+    }
+}
+
+impl From<String> for Agent {
+    fn from(text: String) -> Self {
+        Agent::from_string(&text)
+    }
+}
+
+impl<'a> From<&'a str> for Agent {
+    fn from(text: &'a str) -> Self {
+        Agent::from_string(text)
+    }
+}
+
+fn decode_key(key: &str) -> Option<Vec<u8>> {
+    key.from_base58().ok()
+}
+
+fn invalid(msg: &str) -> serde_json::Error {
+    serde::de::Error::custom(msg)
 }
 
 #[cfg(test)]
@@ -35,5 +203,126 @@ mod tests {
 
         let agent = Agent::from_string("jane");
         assert_eq!(agent.identity.content, "jane".to_string());
+        assert_eq!(agent.public_key(), None);
+        assert_eq!(agent.sign(b"some data"), None);
+    }
+
+    #[test]
+    fn can_convert_into_agent() {
+        assert_eq!(Agent::from("bob".to_string()), Agent::from_string("bob"));
+        assert_eq!(Agent::from("bob"), Agent::from_string("bob"));
+        let agent: Agent = "jane".into();
+        assert_eq!(agent, Agent::from_string("jane"));
+    }
+
+    #[test]
+    fn can_address_agent() {
+        let agent = Agent::from_string("bob");
+        assert_eq!(agent.address(), hash::address("bob"));
+        assert_eq!(agent.address(), Agent::from_string("bob").address());
+        assert_ne!(agent.address(), Agent::from_string("jane").address());
+    }
+
+    #[test]
+    fn can_generate_agent() {
+        let agent = Agent::generate();
+        let public_key = agent.public_key().unwrap();
+        assert_eq!(public_key.len(), 32);
+        assert_eq!(agent.identity.content, public_key.to_base58());
+        assert_ne!(agent, Agent::generate());
+    }
+
+    #[test]
+    fn can_create_agent_from_seed() {
+        let agent = Agent::from_seed(b"some seed");
+        assert_eq!(agent, Agent::from_seed(b"some seed"));
+        assert_eq!(
+            agent.public_key(),
+            Agent::from_seed(b"some seed").public_key()
+        );
+        assert_eq!(
+            agent.identity.content,
+            agent.public_key().unwrap().to_base58()
+        );
+        assert_ne!(
+            agent.public_key(),
+            Agent::from_seed(b"other seed").public_key()
+        );
+
+        let signature = agent.sign(b"some data").unwrap();
+        assert!(Agent::verify(
+            &agent.public_key().unwrap(),
+            b"some data",
+            &signature
+        ));
+    }
+
+    #[test]
+    fn can_sign_and_verify() {
+        let agent = Agent::generate();
+        let other = Agent::generate();
+        let data = b"some data";
+        let signature = agent.sign(data).unwrap();
+
+        assert!(Agent::verify(
+            &agent.public_key().unwrap(),
+            data,
+            &signature
+        ));
+        assert!(!Agent::verify(
+            &other.public_key().unwrap(),
+            data,
+            &signature
+        ));
+        assert!(!Agent::verify(
+            &agent.public_key().unwrap(),
+            b"other data",
+            &signature
+        ));
+        assert!(!Agent::verify(&agent.public_key().unwrap(), data, b"bogus"));
+    }
+
+    #[test]
+    fn json_round_trip_is_lossless() {
+        let agent = Agent::generate();
+        let json = agent.to_json().unwrap();
+        let loaded = Agent::from_json(&json).unwrap();
+        assert_eq!(loaded, agent);
+        assert_eq!(loaded.public_key(), agent.public_key());
+        let signature = loaded.sign(b"some data").unwrap();
+        assert!(Agent::verify(
+            &agent.public_key().unwrap(),
+            b"some data",
+            &signature
+        ));
+
+        let agent = Agent::from_string("bob");
+        assert_eq!(Agent::from_json(&agent.to_json().unwrap()).unwrap(), agent);
+    }
+
+    #[test]
+    fn from_json_rejects_mismatched_keys() {
+        let agent = Agent::generate();
+        let other = Agent::generate();
+        let json = format!(
+            r#"{{"identity": "bob", "public_key": "{}", "secret_key": "{}"}}"#,
+            other.public_key().unwrap().to_base58(),
+            agent.keys.clone().unwrap().secret.to_base58()
+        );
+        assert!(Agent::from_json(&json).is_err());
+
+        let json = format!(
+            r#"{{"identity": "bob", "public_key": "{}"}}"#,
+            agent.public_key().unwrap().to_base58()
+        );
+        assert!(Agent::from_json(&json).is_err());
+        assert!(Agent::from_json("not json").is_err());
+    }
+
+    #[test]
+    fn debug_hides_secret_key() {
+        let agent = Agent::generate();
+        let secret = agent.keys.clone().unwrap().secret.to_base58();
+        assert!(!format!("{:?}", agent).contains(&secret));
     }
 }
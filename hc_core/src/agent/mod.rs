@@ -1,15 +1,73 @@
 pub mod keys;
 
+extern crate chrono;
+
+use self::chrono::{DateTime, Utc};
 use self::keys::Keys;
-use common::entry::Entry;
+use common::entry::{Entry, Header};
 use source_chain::memory::SourceChain;
+use source_chain::{Pair, SourceChain as _SourceChain};
 use state;
-use std::rc::Rc;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::Arc;
+use std::time::SystemTime;
 
-#[derive(Clone, Debug, PartialEq, Default)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
 pub struct AgentState {
     keys: Option<Keys>,
+    // the agent's commits, oldest first, each header linking to the one before
     source_chain: Option<Box<SourceChain>>,
+    // the addresses of the committed entries, which are kept in the context's entry store
+    // (sorted, so that states serialize the same whatever order entries were committed in)
+    entries: BTreeSet<String>,
+    // states saved before links existed have none
+    #[serde(default)]
+    links: Vec<Link>,
+    // the addresses of the committed entries, in the order they were first committed
+    #[serde(default)]
+    commit_order: Vec<String>,
+    // who committed the entry at each address, for entries that were signed
+    #[serde(default)]
+    provenances: HashMap<String, Provenance>,
+    // the address of the entry that replaced each updated entry
+    #[serde(default)]
+    updates: HashMap<String, String>,
+    // the addresses of removed entries, which are kept but no longer returned
+    #[serde(default)]
+    removed: HashSet<String>,
+}
+
+/// the agent that committed an entry, with their signature over its content
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Provenance {
+    /// the address of the committing agent
+    pub author: String,
+    pub public_key: Vec<u8>,
+    pub signature: Vec<u8>,
+    /// when the entry was signed, in RFC3339
+    #[serde(default)]
+    pub timestamp: String,
+}
+
+impl Provenance {
+    /// the provenance of an entry that author is signing now
+    pub fn new(author: &str, public_key: Vec<u8>, signature: Vec<u8>) -> Self {
+        let now: DateTime<Utc> = SystemTime::now().into();
+        Provenance {
+            author: author.to_string(),
+            public_key,
+            signature,
+            timestamp: now.to_rfc3339(),
+        }
+    }
+}
+
+/// a tagged link from the entry at one address to the entry at another
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Link {
+    pub base: String,
+    pub target: String,
+    pub tag: String,
 }
 
 impl AgentState {
@@ -17,24 +75,317 @@ impl AgentState {
         AgentState {
             keys: None,
             source_chain: None,
+            entries: BTreeSet::new(),
+            links: Vec::new(),
+            commit_order: Vec::new(),
+            provenances: HashMap::new(),
+            updates: HashMap::new(),
+            removed: HashSet::new(),
+        }
+    }
+
+    /// whether the entry at address has been committed and not removed
+    pub fn has_entry(&self, address: &str) -> bool {
+        self.entries.contains(address) && !self.removed.contains(address)
+    }
+
+    /// the address of the entry that the entry at address was updated to, if it was updated
+    pub fn updated_address(&self, address: &str) -> Option<String> {
+        self.updates.get(address).cloned()
+    }
+
+    /// the addresses of the entries linked to from base with tag, in the order they were linked
+    pub fn links(&self, base: &str, tag: &str) -> Vec<String> {
+        self.links
+            .iter()
+            .filter(|link| link.base == base && link.tag == tag)
+            .map(|link| link.target.clone())
+            .collect()
+    }
+
+    /// who committed the entry stored at address, if it was signed
+    pub fn provenance(&self, address: &str) -> Option<Provenance> {
+        self.provenances.get(address).cloned()
+    }
+
+    /// check that entry, as stored at address, is signed by the public key recorded
+    /// with the address, which fails for unsigned and tampered entries
+    pub fn verify_entry(&self, address: &str, entry: &Entry) -> bool {
+        match self.provenances.get(address) {
+            Some(provenance) => ::hc_agent::Agent::verify(
+                &provenance.public_key,
+                entry.content().as_bytes(),
+                &provenance.signature,
+            ),
+            None => false,
+        }
+    }
+
+    /// the agent's source chain, in the order entries were committed
+    pub fn chain(&self) -> Vec<Pair> {
+        match self.source_chain {
+            Some(ref chain) => chain.iter().cloned().collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// the addresses of all committed entries, including removed ones
+    pub fn entries(&self) -> &BTreeSet<String> {
+        &self.entries
+    }
+
+    /// the addresses of the committed entries that haven't been removed,
+    /// in the order they were committed
+    pub fn addresses(&self) -> Vec<String> {
+        self.commit_order
+            .iter()
+            .filter(|address| self.has_entry(address))
+            .cloned()
+            .collect()
+    }
+
+    // store entry along with who signed it, if it was signed, appending it to the source
+    // chain; committing a removed entry again brings it back
+    fn commit(&mut self, entry: &Entry, provenance: Option<&Provenance>) {
+        let address = entry.address();
+        let was_removed = self.removed.remove(&address);
+        // committing the same entry again keeps its place in the order,
+        // and the first agent to commit an entry stays its author
+        let is_new = self.entries.insert(address.clone());
+        if is_new {
+            self.commit_order.push(address.clone());
+            if let Some(provenance) = provenance {
+                self.provenances.insert(address, provenance.clone());
+            }
+        }
+        if is_new || was_removed {
+            self.append_to_chain(entry, provenance);
         }
     }
+
+    // the header is made from the action, so that replaying it rebuilds the same chain
+    fn append_to_chain(&mut self, entry: &Entry, provenance: Option<&Provenance>) {
+        let chain = self
+            .source_chain
+            .get_or_insert_with(|| Box::new(SourceChain::new()));
+        let previous = chain.iter().last().map(|pair| pair.header().hash());
+        let header = match provenance {
+            Some(provenance) => {
+                Header::authored(previous, entry, &provenance.author, &provenance.timestamp)
+            }
+            None => Header::new(previous, entry),
+        };
+        chain.push(&Pair::new(&header, entry));
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum Action {
     Commit(Entry),
+    /// commit an entry along with who signed it
+    SignedCommit(Entry, Provenance),
+    LinkEntries {
+        base: String,
+        target: String,
+        tag: String,
+    },
+    /// commit entry as the new version of the entry at old_address,
+    /// which stays committed so that it can still be got
+    UpdateEntry {
+        old_address: String,
+        entry: Entry,
+        provenance: Option<Provenance>,
+    },
+    /// mark the entry at address as removed, so that it is no longer returned
+    RemoveEntry(String),
 }
 
-pub fn reduce(old_state: Rc<AgentState>, action: &state::Action) -> Rc<AgentState> {
+pub fn reduce(old_state: Arc<AgentState>, action: &state::Action) -> Arc<AgentState> {
     match *action {
         state::Action::Agent(ref agent_action) => {
             let mut new_state: AgentState = (*old_state).clone();
             match *agent_action {
-                Action::Commit(ref _entry) => {}
+                Action::Commit(ref entry) => {
+                    new_state.commit(entry, None);
+                }
+                Action::SignedCommit(ref entry, ref provenance) => {
+                    new_state.commit(entry, Some(provenance));
+                }
+                Action::LinkEntries {
+                    ref base,
+                    ref target,
+                    ref tag,
+                } => {
+                    let link = Link {
+                        base: base.clone(),
+                        target: target.clone(),
+                        tag: tag.clone(),
+                    };
+                    // linking the same entries with the same tag twice changes nothing
+                    if !new_state.links.contains(&link) {
+                        new_state.links.push(link);
+                    }
+                }
+                Action::UpdateEntry {
+                    ref old_address,
+                    ref entry,
+                    ref provenance,
+                } => {
+                    new_state.commit(entry, provenance.as_ref());
+                    new_state
+                        .updates
+                        .insert(old_address.clone(), entry.address());
+                }
+                Action::RemoveEntry(ref address) => {
+                    new_state.removed.insert(address.clone());
+                }
             }
-            Rc::new(new_state)
+            Arc::new(new_state)
         }
         _ => old_state,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::super::state::Action::*;
+    use super::*;
+
+    #[test]
+    fn can_reduce_commit_action() {
+        let entry = Entry::new("test_type", "some content");
+        let state = Arc::new(AgentState::new());
+        assert!(!state.has_entry(&entry.address()));
+
+        let reduced_state = reduce(state, &Agent(Action::Commit(entry.clone())));
+        assert!(reduced_state.has_entry(&entry.address()));
+        assert_eq!(reduced_state.addresses(), vec![entry.address()]);
+    }
+
+    #[test]
+    fn commits_are_appended_to_the_source_chain() {
+        let first = Entry::new("test_type", "first");
+        let second = Entry::new("test_type", "second");
+        let mut state = Arc::new(AgentState::new());
+        assert!(state.chain().is_empty());
+
+        for entry in &[first.clone(), second.clone(), first.clone()] {
+            state = reduce(state, &Agent(Action::Commit(entry.clone())));
+        }
+        // committing the same entry again doesn't append it again
+        let chain = state.chain();
+        assert_eq!(chain.len(), 2);
+        assert_eq!(chain[0].entry(), first);
+        assert_eq!(chain[0].header().previous(), None);
+        assert_eq!(chain[1].entry(), second);
+        assert_eq!(chain[1].header().previous(), Some(chain[0].header().hash()));
+        assert_eq!(chain[1].header().entry_type(), "test_type");
+        assert_eq!(chain[1].header().author(), None);
+    }
+
+    #[test]
+    fn can_verify_signed_entries() {
+        let agent = ::hc_agent::Agent::from_seed(b"bob");
+        let entry = Entry::new("test_type", "some content");
+        let address = entry.address();
+        let provenance = Provenance::new(
+            &agent.address(),
+            agent.public_key().unwrap(),
+            agent.sign(b"some content").unwrap(),
+        );
+        let state = reduce(
+            Arc::new(AgentState::new()),
+            &Agent(Action::SignedCommit(entry.clone(), provenance.clone())),
+        );
+        assert_eq!(state.provenance(&address), Some(provenance));
+        assert!(state.verify_entry(&address, &entry));
+        assert!(!state.verify_entry("bogus address", &entry));
+
+        let unsigned = Entry::new("test_type", "unsigned content");
+        let state = reduce(state, &Agent(Action::Commit(unsigned.clone())));
+        assert_eq!(state.provenance(&unsigned.address()), None);
+        assert!(!state.verify_entry(&unsigned.address(), &unsigned));
+
+        let tampered = Entry::new("test_type", "other content");
+        assert!(!state.verify_entry(&address, &tampered));
+    }
+
+    #[test]
+    fn can_list_addresses_in_commit_order() {
+        let entries = vec![
+            Entry::new("person", "zoe"),
+            Entry::new("post", "hello"),
+            Entry::new("person", "alice"),
+            Entry::new("person", "zoe"),
+        ];
+        let mut state = Arc::new(AgentState::new());
+        for entry in &entries {
+            state = reduce(state, &Agent(Action::Commit(entry.clone())));
+        }
+        assert_eq!(
+            state.addresses(),
+            vec![
+                entries[0].address(),
+                entries[1].address(),
+                entries[2].address(),
+            ]
+        );
+    }
+
+    #[test]
+    fn can_reduce_update_and_remove_actions() {
+        let old = Entry::new("post", "helo");
+        let new = Entry::new("post", "hello");
+        let mut state = Arc::new(AgentState::new());
+        for action in &[
+            Action::Commit(old.clone()),
+            Action::UpdateEntry {
+                old_address: old.address(),
+                entry: new.clone(),
+                provenance: None,
+            },
+        ] {
+            state = reduce(state, &Agent(action.clone()));
+        }
+        assert!(state.has_entry(&old.address()));
+        assert!(state.has_entry(&new.address()));
+        assert_eq!(state.updated_address(&old.address()), Some(new.address()));
+        assert_eq!(state.updated_address(&new.address()), None);
+
+        let state = reduce(state, &Agent(Action::RemoveEntry(old.address())));
+        assert!(!state.has_entry(&old.address()));
+        assert_eq!(state.addresses(), vec![new.address()]);
+
+        let state = reduce(state, &Agent(Action::Commit(old.clone())));
+        assert!(state.has_entry(&old.address()));
+        assert_eq!(state.addresses(), vec![old.address(), new.address()]);
+    }
+
+    #[test]
+    fn can_reduce_link_entries_action() {
+        let link = |target: &str, tag: &str| {
+            Agent(Action::LinkEntries {
+                base: "base".to_string(),
+                target: target.to_string(),
+                tag: tag.to_string(),
+            })
+        };
+        let mut state = Arc::new(AgentState::new());
+        assert!(state.links("base", "likes").is_empty());
+
+        for action in &[
+            link("target1", "likes"),
+            link("target2", "likes"),
+            link("target1", "likes"),
+            link("target3", "follows"),
+        ] {
+            state = reduce(state, action);
+        }
+        assert_eq!(
+            state.links("base", "likes"),
+            vec!["target1".to_string(), "target2".to_string()]
+        );
+        assert_eq!(state.links("base", "follows"), vec!["target3".to_string()]);
+        assert!(state.links("target1", "likes").is_empty());
+    }
+}
@@ -1,21 +1,26 @@
+use hc_dna::hash;
+use serde_json;
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash as _Hash, Hasher};
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct Entry {
+    entry_type: String,
     content: String,
     hash: u64,
 }
 
 impl _Hash for Entry {
     fn hash<H: Hasher>(&self, state: &mut H) {
+        self.entry_type.hash(state);
         self.content.hash(state);
     }
 }
 
 impl Entry {
-    pub fn new(content: &str) -> Entry {
+    pub fn new(entry_type: &str, content: &str) -> Entry {
         let mut e = Entry {
+            entry_type: entry_type.to_string(),
             content: content.to_string(),
             hash: 0,
         };
@@ -29,17 +34,32 @@ impl Entry {
         self.hash
     }
 
+    pub fn entry_type(&self) -> String {
+        self.entry_type.clone()
+    }
+
     pub fn content(&self) -> String {
         self.content.clone()
     }
+
+    /// the address an entry is stored at, which is the base58 encoded
+    /// sha2-256 multihash of its type and content
+    pub fn address(&self) -> String {
+        // serializing a pair of strings can't fail
+        hash::address(&serde_json::to_string(&(&self.entry_type, &self.content)).unwrap())
+    }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct Header {
     // these are hashes instead of references so that they can be serialized/validated as data in
     // any/all implementations
     previous: Option<u64>,
     entry: u64,
+    entry_type: String,
+    // who committed the entry and when, which is only known for signed commits
+    author: Option<String>,
+    timestamp: Option<String>,
     hash: u64,
 }
 
@@ -47,14 +67,40 @@ impl _Hash for Header {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.previous.hash(state);
         self.entry.hash(state);
+        self.entry_type.hash(state);
+        self.author.hash(state);
+        self.timestamp.hash(state);
     }
 }
 
 impl Header {
     pub fn new(previous: Option<u64>, entry: &Entry) -> Header {
+        Header::build(previous, entry, None, None)
+    }
+
+    /// a header for entry as committed by the agent at the author address,
+    /// at timestamp (in RFC3339)
+    pub fn authored(previous: Option<u64>, entry: &Entry, author: &str, timestamp: &str) -> Header {
+        Header::build(
+            previous,
+            entry,
+            Some(author.to_string()),
+            Some(timestamp.to_string()),
+        )
+    }
+
+    fn build(
+        previous: Option<u64>,
+        entry: &Entry,
+        author: Option<String>,
+        timestamp: Option<String>,
+    ) -> Header {
         let mut h = Header {
             previous,
             entry: entry.hash(),
+            entry_type: entry.entry_type(),
+            author,
+            timestamp,
             hash: 0,
         };
         let mut hasher = DefaultHasher::new();
@@ -71,10 +117,45 @@ impl Header {
         self.previous
     }
 
+    pub fn entry_type(&self) -> String {
+        self.entry_type.clone()
+    }
+
+    pub fn author(&self) -> Option<String> {
+        self.author.clone()
+    }
+
+    pub fn timestamp(&self) -> Option<String> {
+        self.timestamp.clone()
+    }
+
     pub fn hash(&self) -> u64 {
         self.hash
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct Hash {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn address_depends_on_type_and_content() {
+        let entry = Entry::new("test_type", "some content");
+        assert!(!entry.address().is_empty());
+        assert_eq!(
+            entry.address(),
+            Entry::new("test_type", "some content").address()
+        );
+        assert_ne!(
+            entry.address(),
+            Entry::new("other_type", "some content").address()
+        );
+        assert_ne!(
+            entry.address(),
+            Entry::new("test_type", "other content").address()
+        );
+    }
+}
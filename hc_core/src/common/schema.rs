@@ -0,0 +1,259 @@
+/*!
+schema checks entry content against the JSON Schemas that entry types can declare.
+
+Only the keywords needed to describe entry content are supported: type, enum, const,
+properties, required, additionalProperties, items, minItems, maxItems, minLength,
+maxLength, minimum and maximum. Any other keywords are ignored.
+*/
+
+use error::HolochainError;
+use serde_json::{self, Map, Value};
+
+/// check that content is json conforming to schema, failing with the json pointer
+/// to where it first doesn't, e.g. "/tags/1: expected string, found number"
+pub fn validate(schema: &str, content: &str) -> Result<(), HolochainError> {
+    let schema: Value = serde_json::from_str(schema)
+        .map_err(|e| HolochainError::ValidationFailed(format!("invalid schema: {}", e)))?;
+    let content: Value = serde_json::from_str(content)
+        .map_err(|e| HolochainError::ValidationFailed(format!("content is not json: {}", e)))?;
+    check(&schema, &content, "").map_err(|(path, reason)| {
+        let path = if path.is_empty() { "/" } else { &path };
+        HolochainError::ValidationFailed(format!("{}: {}", path, reason))
+    })
+}
+
+// the json pointer to the value that doesn't conform, and why
+type Failure = (String, String);
+
+fn check(schema: &Value, value: &Value, path: &str) -> Result<(), Failure> {
+    let fail = |reason: String| Err((path.to_string(), reason));
+    let schema = match *schema {
+        Value::Bool(true) => return Ok(()),
+        Value::Bool(false) => return fail("not allowed".to_string()),
+        Value::Object(ref schema) => schema,
+        _ => return fail("schema is not an object".to_string()),
+    };
+
+    if let Some(types) = schema.get("type") {
+        let types: Vec<&str> = match *types {
+            Value::Array(ref types) => types.iter().filter_map(Value::as_str).collect(),
+            ref other => other.as_str().into_iter().collect(),
+        };
+        if !types.iter().any(|name| has_type(value, name)) {
+            return fail(format!(
+                "expected {}, found {}",
+                types.join(" or "),
+                type_name(value)
+            ));
+        }
+    }
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(value) {
+            return fail(format!("{} is not one of {}", value, schema["enum"]));
+        }
+    }
+    if let Some(expected) = schema.get("const") {
+        if value != expected {
+            return fail(format!("expected {}, found {}", expected, value));
+        }
+    }
+
+    match *value {
+        Value::Object(ref fields) => {
+            if let Some(required) = schema.get("required").and_then(Value::as_array) {
+                for name in required.iter().filter_map(Value::as_str) {
+                    if !fields.contains_key(name) {
+                        return fail(format!("missing required property {}", name));
+                    }
+                }
+            }
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for (name, field) in fields {
+                let field_schema = properties
+                    .and_then(|properties| properties.get(name))
+                    .or_else(|| schema.get("additionalProperties"));
+                if let Some(field_schema) = field_schema {
+                    check(field_schema, field, &format!("{}/{}", path, escape(name)))?;
+                }
+            }
+        }
+        Value::Array(ref items) => {
+            check_count(schema, "minItems", "maxItems", items.len(), "items").or_else(fail)?;
+            if let Some(item_schema) = schema.get("items") {
+                for (index, item) in items.iter().enumerate() {
+                    check(item_schema, item, &format!("{}/{}", path, index))?;
+                }
+            }
+        }
+        Value::String(ref text) => {
+            check_count(
+                schema,
+                "minLength",
+                "maxLength",
+                text.chars().count(),
+                "characters",
+            )
+            .or_else(fail)?;
+        }
+        Value::Number(ref number) => {
+            let number = number.as_f64().unwrap_or(0.0);
+            if let Some(minimum) = schema.get("minimum").and_then(Value::as_f64) {
+                if number < minimum {
+                    return fail(format!(
+                        "{} is less than the minimum of {}",
+                        number, minimum
+                    ));
+                }
+            }
+            if let Some(maximum) = schema.get("maximum").and_then(Value::as_f64) {
+                if number > maximum {
+                    return fail(format!(
+                        "{} is more than the maximum of {}",
+                        number, maximum
+                    ));
+                }
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+// checks how many of something a value has against the schema's bounds for it
+fn check_count(
+    schema: &Map<String, Value>,
+    min: &str,
+    max: &str,
+    count: usize,
+    what: &str,
+) -> Result<(), String> {
+    let count = count as u64;
+    if let Some(min) = schema.get(min).and_then(Value::as_u64) {
+        if count < min {
+            return Err(format!(
+                "expected at least {} {}, found {}",
+                min, what, count
+            ));
+        }
+    }
+    if let Some(max) = schema.get(max).and_then(Value::as_u64) {
+        if count > max {
+            return Err(format!(
+                "expected at most {} {}, found {}",
+                max, what, count
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn has_type(value: &Value, name: &str) -> bool {
+    if name != "integer" {
+        return type_name(value) == name;
+    }
+    value.is_i64() || value.is_u64() || value.as_f64().map(|n| n.fract() == 0.0).unwrap_or(false)
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match *value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+// escapes a property name for use in a json pointer
+fn escape(name: &str) -> String {
+    name.replace('~', "~0").replace('/', "~1")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POST_SCHEMA: &str = r#"{
+        "type": "object",
+        "required": ["title"],
+        "properties": {
+            "title": {"type": "string", "minLength": 1, "maxLength": 10},
+            "stars": {"type": "integer", "minimum": 0, "maximum": 5},
+            "tags": {"type": "array", "items": {"type": "string"}, "maxItems": 2},
+            "status": {"enum": ["draft", "published"]}
+        },
+        "additionalProperties": false
+    }"#;
+
+    fn failure(content: &str) -> String {
+        match validate(POST_SCHEMA, content) {
+            Err(HolochainError::ValidationFailed(reason)) => reason,
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn conforming_content_is_valid() {
+        assert_eq!(validate(POST_SCHEMA, r#"{"title": "hello"}"#), Ok(()));
+        assert_eq!(
+            validate(
+                POST_SCHEMA,
+                r#"{"title": "hello", "stars": 5, "tags": ["a", "b"], "status": "draft"}"#
+            ),
+            Ok(())
+        );
+        assert_eq!(validate("{}", "[1, null]"), Ok(()));
+        assert_eq!(validate("true", "\"anything\""), Ok(()));
+    }
+
+    #[test]
+    fn failures_say_where_content_does_not_conform() {
+        assert_eq!(failure("[]"), "/: expected object, found array");
+        assert_eq!(failure("{}"), "/: missing required property title");
+        assert_eq!(
+            failure(r#"{"title": ""}"#),
+            "/title: expected at least 1 characters, found 0"
+        );
+        assert_eq!(
+            failure(r#"{"title": "hi", "stars": 2.5}"#),
+            "/stars: expected integer, found number"
+        );
+        assert_eq!(
+            failure(r#"{"title": "hi", "stars": 6}"#),
+            "/stars: 6 is more than the maximum of 5"
+        );
+        assert_eq!(
+            failure(r#"{"title": "hi", "tags": ["a", 1]}"#),
+            "/tags/1: expected string, found number"
+        );
+        assert_eq!(
+            failure(r#"{"title": "hi", "tags": ["a", "b", "c"]}"#),
+            "/tags: expected at most 2 items, found 3"
+        );
+        assert_eq!(
+            failure(r#"{"title": "hi", "status": "deleted"}"#),
+            r#"/status: "deleted" is not one of ["draft","published"]"#
+        );
+        assert_eq!(
+            failure(r#"{"title": "hi", "a/b": 1}"#),
+            "/a~1b: not allowed"
+        );
+    }
+
+    #[test]
+    fn content_and_schema_must_be_json() {
+        match validate(POST_SCHEMA, "not json") {
+            Err(HolochainError::ValidationFailed(reason)) => {
+                assert!(reason.starts_with("content is not json"))
+            }
+            _ => assert!(false),
+        };
+        match validate("{bogus", "{}") {
+            Err(HolochainError::ValidationFailed(reason)) => {
+                assert!(reason.starts_with("invalid schema"))
+            }
+            _ => assert!(false),
+        };
+    }
+}
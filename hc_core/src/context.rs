@@ -1,10 +1,14 @@
-extern crate hc_agent;
-
-use self::hc_agent::Agent;
+use entry_store::{EntryStore, SimpleEntryStore};
 use error::HolochainError;
-use logger::Logger;
-use persister::Persister;
-use std::sync::{Arc, Mutex};
+use hc_agent::Agent;
+use logger::{LogLevel, Logger, SimpleLogger};
+use network::dht::DhtStore;
+use persister::{Persister, SimplePersister};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// A callback run when an application is initialized, to set up its initial state.
+/// Stands in for the dna's genesis function until the ribosome can execute it.
+pub type Genesis = Arc<Fn(&Context) -> Result<(), HolochainError> + Send + Sync>;
 
 /// Context holds those aspects of the outside world that a Holochain instance needs to operate
 #[derive(Clone)]
@@ -12,18 +16,350 @@ pub struct Context {
     pub agent: Agent,
     pub logger: Arc<Mutex<Logger>>,
     pub persister: Arc<Mutex<Persister>>,
+    /// where committed entries are kept, and got from
+    pub entry_store: Arc<Mutex<EntryStore>>,
+    /// where committed entries are published, and fetched from when they aren't held locally;
+    /// without one the instance keeps its entries to itself
+    pub dht: Option<Arc<Mutex<DhtStore>>>,
+    /// logged messages are prefixed with this, to tell instances apart in a shared logger
+    pub instance_name: Option<String>,
+    pub genesis: Option<Genesis>,
 }
 
 impl Context {
+    /// run the genesis callback, if there is one
+    pub fn genesis(&self) -> Result<(), HolochainError> {
+        match self.genesis {
+            Some(ref genesis) => genesis(self),
+            None => Ok(()),
+        }
+    }
+
+    /// a copy of this context, sharing its logger and persister, that logs as the named instance
+    pub fn named(&self, instance_name: &str) -> Self {
+        Context {
+            instance_name: Some(instance_name.to_string()),
+            ..self.clone()
+        }
+    }
+
+    /// a copy of this context, sharing its logger and persister, that acts as another agent,
+    /// e.g. to simulate several agents of the same application
+    pub fn with_agent(&self, agent: Agent) -> Arc<Context> {
+        Arc::new(Context {
+            agent,
+            ..self.clone()
+        })
+    }
+
     // helper function to make it easier to call the logger
     pub fn log(&self, msg: &str) -> Result<(), HolochainError> {
-        let result = self.logger.lock();
-        match result {
-            Err(_) => return Err(HolochainError::LoggingError),
-            Ok(mut logger) => {
-                logger.log(msg.to_string());
-            }
-        }
+        self.log_at(LogLevel::Info, msg)
+    }
+
+    // helper function to make it easier to call the logger at a specific level
+    pub fn log_at(&self, level: LogLevel, msg: &str) -> Result<(), HolochainError> {
+        let instance_name = self.instance_name.as_deref();
+        lock_recovering(&self.logger).log_from(instance_name, level, msg.to_string());
         Ok(())
     }
+
+    /// lock the persister, recovering it if a thread panicked while holding it
+    pub fn lock_persister(&self) -> MutexGuard<'_, Persister + 'static> {
+        lock_recovering(&self.persister)
+    }
+
+    /// lock the entry store, recovering it if a thread panicked while holding it
+    pub fn lock_entry_store(&self) -> MutexGuard<'_, EntryStore + 'static> {
+        lock_recovering(&self.entry_store)
+    }
+
+    /// lock the dht, if there is one, recovering it if a thread panicked while holding it
+    pub fn lock_dht(&self) -> Option<MutexGuard<'_, DhtStore + 'static>> {
+        self.dht.as_ref().map(|dht| lock_recovering(dht))
+    }
+}
+
+/// a context for trying things out, acting as an anonymous agent with a SimpleLogger,
+/// SimplePersister and SimpleEntryStore; use ContextBuilder to say who the agent is
+impl Default for Context {
+    fn default() -> Self {
+        Context {
+            agent: Agent::from_string("anonymous"),
+            logger: Arc::new(Mutex::new(SimpleLogger::new())),
+            persister: Arc::new(Mutex::new(SimplePersister::new())),
+            entry_store: Arc::new(Mutex::new(SimpleEntryStore::new())),
+            dht: None,
+            instance_name: None,
+            genesis: None,
+        }
+    }
+}
+
+// a panic while the logger, persister or entry store was locked shouldn't take down
+// every instance using it, so poisoned locks are taken over rather than failing
+fn lock_recovering<T: ?Sized>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// builds a Context, defaulting the logger, persister and entry store to SimpleLogger,
+/// SimplePersister and SimpleEntryStore
+#[derive(Default)]
+pub struct ContextBuilder {
+    agent: Option<Agent>,
+    logger: Option<Arc<Mutex<Logger>>>,
+    persister: Option<Arc<Mutex<Persister>>>,
+    entry_store: Option<Arc<Mutex<EntryStore>>>,
+    dht: Option<Arc<Mutex<DhtStore>>>,
+    genesis: Option<Genesis>,
+}
+
+impl ContextBuilder {
+    pub fn new() -> Self {
+        ContextBuilder {
+            agent: None,
+            logger: None,
+            persister: None,
+            entry_store: None,
+            dht: None,
+            genesis: None,
+        }
+    }
+
+    pub fn with_agent(mut self, agent: Agent) -> Self {
+        self.agent = Some(agent);
+        self
+    }
+
+    pub fn with_logger<L: Logger + 'static>(mut self, logger: L) -> Self {
+        self.logger = Some(Arc::new(Mutex::new(logger)));
+        self
+    }
+
+    pub fn with_persister<P: Persister + 'static>(mut self, persister: P) -> Self {
+        self.persister = Some(Arc::new(Mutex::new(persister)));
+        self
+    }
+
+    pub fn with_entry_store<S: EntryStore + 'static>(mut self, entry_store: S) -> Self {
+        self.entry_store = Some(Arc::new(Mutex::new(entry_store)));
+        self
+    }
+
+    pub fn with_dht<D: DhtStore + 'static>(mut self, dht: D) -> Self {
+        self.dht = Some(Arc::new(Mutex::new(dht)));
+        self
+    }
+
+    pub fn with_genesis<F>(mut self, genesis: F) -> Self
+    where
+        F: Fn(&Context) -> Result<(), HolochainError> + Send + Sync + 'static,
+    {
+        self.genesis = Some(Arc::new(genesis));
+        self
+    }
+
+    /// fails if no agent was given, as there is no sensible default for one
+    pub fn build(self) -> Result<Arc<Context>, HolochainError> {
+        let agent = match self.agent {
+            Some(agent) => agent,
+            None => return Err(HolochainError::new("a context needs an agent")),
+        };
+        Ok(Arc::new(Context {
+            agent,
+            logger: self
+                .logger
+                .unwrap_or_else(|| Arc::new(Mutex::new(SimpleLogger::new()))),
+            persister: self
+                .persister
+                .unwrap_or_else(|| Arc::new(Mutex::new(SimplePersister::new()))),
+            entry_store: self
+                .entry_store
+                .unwrap_or_else(|| Arc::new(Mutex::new(SimpleEntryStore::new()))),
+            dht: self.dht,
+            instance_name: None,
+            genesis: self.genesis,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_log_at_levels() {
+        let logger = Arc::new(Mutex::new(SimpleLogger::new()));
+        let context = Context {
+            agent: Agent::from_string("bob"),
+            logger: logger.clone(),
+            persister: Arc::new(Mutex::new(SimplePersister::new())),
+            entry_store: Arc::new(Mutex::new(SimpleEntryStore::new())),
+            dht: None,
+            instance_name: None,
+            genesis: None,
+        };
+
+        context.log("info by default").unwrap();
+        context.log_at(LogLevel::Error, "an error").unwrap();
+
+        let lines = logger.lock().unwrap().dump();
+        assert!(lines[0].starts_with("[INFO] "));
+        assert!(lines[0].ends_with(" info by default"));
+        assert!(lines[1].starts_with("[ERROR] "));
+        assert!(lines[1].ends_with(" an error"));
+    }
+
+    #[test]
+    fn named_context_prefixes_log_messages() {
+        let context = ContextBuilder::new()
+            .with_agent(Agent::from_string("bob"))
+            .with_logger(SimpleLogger::new().with_timestamps(false))
+            .build()
+            .unwrap();
+        let named = context.named("TestApp");
+        named.log("from the instance").unwrap();
+        context.log("from the container").unwrap();
+
+        // both share the same logger
+        let lines = named.logger.lock().unwrap().dump();
+        assert_eq!(lines[0], "[INFO] [TestApp] from the instance");
+        assert_eq!(lines[1], "[INFO] from the container");
+    }
+
+    #[test]
+    fn can_derive_contexts_for_other_agents() {
+        let base = ContextBuilder::new()
+            .with_agent(Agent::from_string("bob"))
+            .build()
+            .unwrap();
+        let alice = base.with_agent(Agent::from_string("alice"));
+        let jane = base.with_agent(Agent::from_string("jane"));
+
+        assert_eq!(alice.agent, Agent::from_string("alice"));
+        assert_eq!(jane.agent, Agent::from_string("jane"));
+        assert!(Arc::ptr_eq(&alice.logger, &jane.logger));
+        assert!(Arc::ptr_eq(&alice.logger, &base.logger));
+        assert!(Arc::ptr_eq(&alice.persister, &jane.persister));
+        assert!(Arc::ptr_eq(&alice.persister, &base.persister));
+    }
+
+    #[test]
+    fn named_context_logs_instance_name_as_json() {
+        let context = ContextBuilder::new()
+            .with_agent(Agent::from_string("bob"))
+            .with_logger(::logger::JsonLogger::new())
+            .build()
+            .unwrap();
+        context.named("TestApp").log("from the instance").unwrap();
+
+        let lines = context.logger.lock().unwrap().dump();
+        let line: ::serde_json::Value = ::serde_json::from_str(&lines[0]).unwrap();
+        assert_eq!(line["instance"], "TestApp");
+        assert_eq!(line["message"], "from the instance");
+    }
+
+    #[test]
+    fn can_build_with_defaults() {
+        let agent = Agent::from_string("bob");
+        let context = ContextBuilder::new()
+            .with_agent(agent.clone())
+            .build()
+            .unwrap();
+        assert_eq!(context.agent, agent);
+
+        context.log("to the default logger").unwrap();
+        let lines = context.logger.lock().unwrap().dump();
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].ends_with(" to the default logger"));
+        assert_eq!(context.persister.lock().unwrap().load(), Ok(None));
+    }
+
+    #[test]
+    fn can_build_fully_specified() {
+        let agent = Agent::from_string("bob");
+        let mut persister = SimplePersister::new();
+        persister.save(&::state::State::new()).unwrap();
+        let entry = ::common::entry::Entry::new("person", "alice");
+        let mut entry_store = SimpleEntryStore::new();
+        entry_store.put(&entry).unwrap();
+        let context = ContextBuilder::new()
+            .with_agent(agent.clone())
+            .with_logger(SimpleLogger::new().with_level(LogLevel::Warn))
+            .with_persister(persister)
+            .with_entry_store(entry_store)
+            .build()
+            .unwrap();
+        assert_eq!(context.agent, agent);
+
+        context.log("dropped by the given logger").unwrap();
+        assert!(context.logger.lock().unwrap().dump().is_empty());
+        assert_eq!(
+            context.persister.lock().unwrap().load(),
+            Ok(Some(::state::State::new()))
+        );
+        assert_eq!(
+            context.entry_store.lock().unwrap().get(&entry.address()),
+            Ok(Some(entry))
+        );
+    }
+
+    #[test]
+    fn can_run_genesis() {
+        let context = ContextBuilder::new()
+            .with_agent(Agent::from_string("bob"))
+            .build()
+            .unwrap();
+        assert_eq!(context.genesis(), Ok(()));
+
+        let context = ContextBuilder::new()
+            .with_agent(Agent::from_string("bob"))
+            .with_genesis(|context| context.log("genesis"))
+            .build()
+            .unwrap();
+        assert_eq!(context.genesis(), Ok(()));
+        assert!(context.logger.lock().unwrap().dump()[0].ends_with(" genesis"));
+    }
+
+    #[test]
+    fn build_without_agent_fails() {
+        match ContextBuilder::new().build() {
+            Err(HolochainError::ErrorGeneric(_)) => assert!(true),
+            _ => assert!(false),
+        };
+    }
+
+    #[test]
+    fn logging_survives_a_poisoned_logger() {
+        let context = ContextBuilder::new()
+            .with_agent(Agent::from_string("bob"))
+            .build()
+            .unwrap();
+        let logger = context.logger.clone();
+        let result = ::std::thread::spawn(move || {
+            let _guard = logger.lock().unwrap();
+            panic!("panicking while logging");
+        })
+        .join();
+        assert!(result.is_err());
+        assert!(context.logger.is_poisoned());
+
+        assert_eq!(context.log("still logging"), Ok(()));
+        let lines = context
+            .logger
+            .lock()
+            .unwrap_or_else(|p| p.into_inner())
+            .dump();
+        assert!(lines.last().unwrap().ends_with(" still logging"));
+
+        let persister = context.persister.clone();
+        let _ = ::std::thread::spawn(move || {
+            let _guard = persister.lock().unwrap();
+            panic!("panicking while persisting");
+        })
+        .join();
+        assert_eq!(context.lock_persister().load(), Ok(None));
+    }
 }
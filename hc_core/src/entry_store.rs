@@ -0,0 +1,265 @@
+/*!
+entry_store holds committed entries outside of the state, so that apps with many or large
+entries can keep them wherever suits them (in memory, in files, in a database).
+*/
+
+use common::entry::Entry;
+use error::HolochainError;
+use serde_json;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, ErrorKind, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// trait that defines where committed entries are kept
+/// (stores must be Send, as instances process actions on their own thread)
+pub trait EntryStore: Send {
+    /// store entry at its address; storing an entry that is already stored changes nothing
+    fn put(&mut self, entry: &Entry) -> Result<(), HolochainError>;
+    /// the entry stored at address, if there is one
+    fn get(&self, address: &str) -> Result<Option<Entry>, HolochainError>;
+    /// the stored entries of entry_type, in the order they were first stored
+    fn all_of_type(&self, entry_type: &str) -> Result<Vec<Entry>, HolochainError>;
+}
+
+/// keeps entries in memory, so they are lost when the process exits
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct SimpleEntryStore {
+    entries: HashMap<String, Entry>,
+    order: Vec<String>,
+}
+
+impl EntryStore for SimpleEntryStore {
+    fn put(&mut self, entry: &Entry) -> Result<(), HolochainError> {
+        let address = entry.address();
+        if self
+            .entries
+            .insert(address.clone(), entry.clone())
+            .is_none()
+        {
+            self.order.push(address);
+        }
+        Ok(())
+    }
+
+    fn get(&self, address: &str) -> Result<Option<Entry>, HolochainError> {
+        Ok(self.entries.get(address).cloned())
+    }
+
+    fn all_of_type(&self, entry_type: &str) -> Result<Vec<Entry>, HolochainError> {
+        Ok(self
+            .order
+            .iter()
+            .filter_map(|address| self.entries.get(address))
+            .filter(|entry| entry.entry_type() == entry_type)
+            .cloned()
+            .collect())
+    }
+}
+
+impl SimpleEntryStore {
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+/// appends entries as lines of json to a file, so they survive the process exiting;
+/// where each entry's line starts is indexed in memory, so entries are read one at a time
+#[derive(Clone, Debug, PartialEq)]
+pub struct FileEntryStore {
+    path: PathBuf,
+    // read from the file when first needed, then kept up to date as entries are put
+    index: RefCell<Option<Index>>,
+}
+
+// where the stored entries are in the file
+#[derive(Clone, Debug, PartialEq, Default)]
+struct Index {
+    offsets: HashMap<String, u64>,
+    // the address and type of each stored entry, in the order they were stored
+    order: Vec<(String, String)>,
+}
+
+impl Index {
+    fn insert(&mut self, entry: &Entry, offset: u64) {
+        let address = entry.address();
+        self.offsets.insert(address.clone(), offset);
+        self.order.push((address, entry.entry_type()));
+    }
+}
+
+impl EntryStore for FileEntryStore {
+    fn put(&mut self, entry: &Entry) -> Result<(), HolochainError> {
+        self.load_index()?;
+        let address = entry.address();
+        let index = self.index.get_mut().get_or_insert_with(Index::default);
+        if index.offsets.contains_key(&address) {
+            return Ok(());
+        }
+        let line = serde_json::to_string(entry)?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        let offset = file.seek(SeekFrom::End(0))?;
+        writeln!(file, "{}", line)?;
+        index.insert(entry, offset);
+        Ok(())
+    }
+
+    fn get(&self, address: &str) -> Result<Option<Entry>, HolochainError> {
+        self.load_index()?;
+        let offset = match self.index.borrow().as_ref() {
+            Some(index) => index.offsets.get(address).cloned(),
+            None => None,
+        };
+        match offset {
+            Some(offset) => {
+                let mut reader = BufReader::new(File::open(&self.path)?);
+                Ok(Some(read_entry_at(&mut reader, offset)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn all_of_type(&self, entry_type: &str) -> Result<Vec<Entry>, HolochainError> {
+        self.load_index()?;
+        let offsets: Vec<u64> = match self.index.borrow().as_ref() {
+            Some(index) => index
+                .order
+                .iter()
+                .filter(|(_, stored_type)| stored_type.as_str() == entry_type)
+                .filter_map(|(address, _)| index.offsets.get(address).cloned())
+                .collect(),
+            None => Vec::new(),
+        };
+        if offsets.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut reader = BufReader::new(File::open(&self.path)?);
+        offsets
+            .into_iter()
+            .map(|offset| read_entry_at(&mut reader, offset))
+            .collect()
+    }
+}
+
+impl FileEntryStore {
+    pub fn new(path: &Path) -> Self {
+        FileEntryStore {
+            path: path.to_path_buf(),
+            index: RefCell::new(None),
+        }
+    }
+
+    // reads the file to index the stored entries, unless it has been already;
+    // a missing file means none are stored
+    fn load_index(&self) -> Result<(), HolochainError> {
+        if self.index.borrow().is_some() {
+            return Ok(());
+        }
+        let mut index = Index::default();
+        match File::open(&self.path) {
+            Err(ref e) if e.kind() == ErrorKind::NotFound => {}
+            file => {
+                let mut reader = BufReader::new(file?);
+                let mut offset = 0;
+                let mut line = String::new();
+                loop {
+                    line.clear();
+                    let read = reader.read_line(&mut line)?;
+                    if read == 0 {
+                        break;
+                    }
+                    let entry: Entry = serde_json::from_str(&line)?;
+                    index.insert(&entry, offset);
+                    offset += read as u64;
+                }
+            }
+        }
+        *self.index.borrow_mut() = Some(index);
+        Ok(())
+    }
+}
+
+// reads the entry whose line starts at offset
+fn read_entry_at(reader: &mut BufReader<File>, offset: u64) -> Result<Entry, HolochainError> {
+    reader.seek(SeekFrom::Start(offset))?;
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    Ok(serde_json::from_str(&line)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+
+    // stores entries and checks they can be got back, by address and by type
+    fn check_store(store: &mut EntryStore) {
+        let alice = Entry::new("person", "alice");
+        let post = Entry::new("post", "hello");
+        let bob = Entry::new("person", "bob");
+        assert_eq!(store.get(&alice.address()), Ok(None));
+        assert_eq!(store.all_of_type("person"), Ok(Vec::new()));
+
+        for entry in &[&alice, &post, &bob, &alice] {
+            store.put(entry).unwrap();
+        }
+        assert_eq!(store.get(&alice.address()), Ok(Some(alice.clone())));
+        assert_eq!(store.get(&post.address()), Ok(Some(post.clone())));
+        assert_eq!(store.get("bogus address"), Ok(None));
+        assert_eq!(store.all_of_type("person"), Ok(vec![alice, bob]));
+        assert_eq!(store.all_of_type("post"), Ok(vec![post]));
+        assert_eq!(store.all_of_type("bogus"), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn simple_entry_store_can_store_entries() {
+        check_store(&mut SimpleEntryStore::new());
+    }
+
+    // a path in the temp dir that is unique to this test run
+    fn test_path(name: &str) -> PathBuf {
+        let file_name = format!("hc_core_entries_{}_{}.jsonl", name, ::std::process::id());
+        let path = env::temp_dir().join(file_name);
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn file_entry_store_can_store_entries() {
+        let path = test_path("store");
+        check_store(&mut FileEntryStore::new(&path));
+
+        // a new store on the same path sees the stored entries, and stores more after them
+        let mut store = FileEntryStore::new(&path);
+        let alice = Entry::new("person", "alice");
+        assert_eq!(store.get(&alice.address()), Ok(Some(alice.clone())));
+        let carol = Entry::new("person", "carol");
+        store.put(&carol).unwrap();
+        assert_eq!(store.get(&carol.address()), Ok(Some(carol.clone())));
+        assert_eq!(
+            store.all_of_type("person"),
+            Ok(vec![alice, Entry::new("person", "bob"), carol])
+        );
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn file_entry_store_fails_on_corrupt_file() {
+        let path = test_path("corrupt");
+        fs::write(&path, "not json\n").unwrap();
+
+        let store = FileEntryStore::new(&path);
+        match store.get("bogus address") {
+            Err(HolochainError::SerializationError(_)) => {}
+            _ => assert!(false),
+        };
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,43 +1,207 @@
+use hc_dna::error::DnaError;
+use serde_json;
 use std::error::Error;
 use std::fmt;
+use std::io;
 
 /// module for holding Holochain specific errors
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub enum HolochainError {
     ErrorGeneric(String),
     InstanceNotActive,
     InstanceActive,
     NotImplemented,
     LoggingError,
+    ZomeNotFound(String),
+    InvalidDna(String),
+    ZomeFunctionFailed {
+        zome: String,
+        function: String,
+        reason: String,
+    },
+    ValidationFailed(String),
+    QueueFull,
+    ZomeFunctionNotFound {
+        zome: String,
+        function: String,
+    },
+    CapabilityDenied {
+        zome: String,
+        function: String,
+    },
+    EntryNotFound(String),
+    DnaVersionMismatch {
+        expected: String,
+        found: String,
+    },
+    Timeout,
+    NotInitialized,
+    SerializationError(String),
+    IoError(String),
+    Cancelled,
+    ActionRaceDetected {
+        dispatched: usize,
+        consumed: usize,
+    },
+    PayloadTooLarge {
+        size: usize,
+        limit: usize,
+    },
+    SnapshotVersionMismatch {
+        expected: u64,
+        found: u64,
+    },
+    WouldBlock,
 }
 
 impl HolochainError {
     pub fn new(msg: &str) -> HolochainError {
         HolochainError::ErrorGeneric(msg.to_string())
     }
+
+    /// the name of the variant, e.g. "InstanceActive", for containers to map errors
+    /// to their own codes; names are kept stable across versions, unlike the display text
+    pub fn variant_name(&self) -> &'static str {
+        match self {
+            ErrorGeneric(_) => "ErrorGeneric",
+            InstanceNotActive => "InstanceNotActive",
+            InstanceActive => "InstanceActive",
+            NotImplemented => "NotImplemented",
+            LoggingError => "LoggingError",
+            ZomeNotFound(_) => "ZomeNotFound",
+            InvalidDna(_) => "InvalidDna",
+            ZomeFunctionFailed { .. } => "ZomeFunctionFailed",
+            ValidationFailed(_) => "ValidationFailed",
+            QueueFull => "QueueFull",
+            ZomeFunctionNotFound { .. } => "ZomeFunctionNotFound",
+            CapabilityDenied { .. } => "CapabilityDenied",
+            EntryNotFound(_) => "EntryNotFound",
+            DnaVersionMismatch { .. } => "DnaVersionMismatch",
+            Timeout => "Timeout",
+            NotInitialized => "NotInitialized",
+            SerializationError(_) => "SerializationError",
+            IoError(_) => "IoError",
+            Cancelled => "Cancelled",
+            ActionRaceDetected { .. } => "ActionRaceDetected",
+            PayloadTooLarge { .. } => "PayloadTooLarge",
+            SnapshotVersionMismatch { .. } => "SnapshotVersionMismatch",
+            WouldBlock => "WouldBlock",
+        }
+    }
 }
 
+use self::HolochainError::*;
+
 impl fmt::Display for HolochainError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self)
+        match self {
+            ErrorGeneric(err_msg) => write!(f, "{}", err_msg),
+            NotImplemented => write!(f, "not implemented"),
+            InstanceNotActive => write!(f, "instance is not active"),
+            InstanceActive => write!(f, "instance is already active"),
+            LoggingError => write!(f, "logging failed"),
+            ZomeNotFound(zome_name) => write!(f, "zome not found: {}", zome_name),
+            InvalidDna(err_msg) => write!(f, "invalid dna: {}", err_msg),
+            ZomeFunctionFailed {
+                zome,
+                function,
+                reason,
+            } => write!(f, "{} in zome {} failed: {}", function, zome, reason),
+            ValidationFailed(reason) => write!(f, "validation failed: {}", reason),
+            QueueFull => write!(f, "action queue is full"),
+            ZomeFunctionNotFound { zome, function } => {
+                write!(f, "function not found in zome {}: {}", zome, function)
+            }
+            CapabilityDenied { zome, function } => {
+                write!(f, "capability denied for {} in zome {}", function, zome)
+            }
+            EntryNotFound(address) => write!(f, "entry not found: {}", address),
+            DnaVersionMismatch { expected, found } => write!(
+                f,
+                "dna version mismatch: expected {}, found {}",
+                expected, found
+            ),
+            Timeout => write!(f, "timed out"),
+            NotInitialized => write!(f, "instance is not initialized"),
+            SerializationError(err_msg) => write!(f, "serialization failed: {}", err_msg),
+            IoError(err_msg) => write!(f, "io failed: {}", err_msg),
+            Cancelled => write!(f, "call was cancelled"),
+            ActionRaceDetected {
+                dispatched,
+                consumed,
+            } => write!(
+                f,
+                "action race detected: dispatched action {} but consumed {}",
+                dispatched, consumed
+            ),
+            PayloadTooLarge { size, limit } => write!(
+                f,
+                "payload too large: {} bytes is over the limit of {}",
+                size, limit
+            ),
+            SnapshotVersionMismatch { expected, found } => write!(
+                f,
+                "snapshot version mismatch: expected {}, found {}",
+                expected, found
+            ),
+            WouldBlock => write!(f, "another thread is consuming actions"),
+        }
     }
 }
 
-use self::HolochainError::*;
-
 impl Error for HolochainError {
     fn description(&self) -> &str {
         match self {
             ErrorGeneric(err_msg) => &err_msg,
             NotImplemented => "not implemented",
-            InstanceNotActive => "the instance is not active",
-            InstanceActive => "the instance is active",
+            InstanceNotActive => "instance is not active",
+            InstanceActive => "instance is already active",
             LoggingError => "logging failed",
+            ZomeNotFound(_) => "zome not found",
+            InvalidDna(_) => "invalid dna",
+            ZomeFunctionFailed { .. } => "zome function failed",
+            ValidationFailed(_) => "validation failed",
+            QueueFull => "action queue is full",
+            ZomeFunctionNotFound { .. } => "zome function not found",
+            CapabilityDenied { .. } => "capability denied",
+            EntryNotFound(_) => "entry not found",
+            DnaVersionMismatch { .. } => "dna version mismatch",
+            Timeout => "timed out",
+            NotInitialized => "instance is not initialized",
+            SerializationError(_) => "serialization failed",
+            IoError(_) => "io failed",
+            Cancelled => "call was cancelled",
+            ActionRaceDetected { .. } => "action race detected",
+            PayloadTooLarge { .. } => "payload too large",
+            SnapshotVersionMismatch { .. } => "snapshot version mismatch",
+            WouldBlock => "another thread is consuming actions",
         }
     }
 }
 
+impl From<DnaError> for HolochainError {
+    fn from(err: DnaError) -> Self {
+        match err {
+            DnaError::Io(_) => IoError(err.to_string()),
+            DnaError::Parse(_) | DnaError::Serialize(_) => SerializationError(err.to_string()),
+            DnaError::Invalid(err_msg) => InvalidDna(err_msg),
+        }
+    }
+}
+
+impl From<io::Error> for HolochainError {
+    fn from(err: io::Error) -> Self {
+        IoError(err.to_string())
+    }
+}
+
+impl From<serde_json::Error> for HolochainError {
+    fn from(err: serde_json::Error) -> Self {
+        SerializationError(err.to_string())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -81,4 +245,225 @@ mod tests {
         };
         assert_eq!(result, ())
     }
+
+    #[test]
+    fn can_name_variants() {
+        assert_eq!(HolochainError::new("borked").variant_name(), "ErrorGeneric");
+        assert_eq!(
+            HolochainError::InstanceActive.variant_name(),
+            "InstanceActive"
+        );
+        assert_eq!(
+            HolochainError::NotImplemented.variant_name(),
+            "NotImplemented"
+        );
+        assert_eq!(
+            HolochainError::ZomeFunctionNotFound {
+                zome: "test_zome".to_string(),
+                function: "bogus".to_string(),
+            }
+            .variant_name(),
+            "ZomeFunctionNotFound"
+        );
+        assert_eq!(
+            HolochainError::EntryNotFound("QmBogus".to_string()).variant_name(),
+            "EntryNotFound"
+        );
+    }
+
+    #[test]
+    fn can_display() {
+        assert_eq!(format!("{}", HolochainError::new("borked")), "borked");
+        assert_eq!(
+            format!("{}", HolochainError::NotImplemented),
+            "not implemented"
+        );
+        assert_eq!(
+            format!("{}", HolochainError::InstanceNotActive),
+            "instance is not active"
+        );
+        assert_eq!(
+            format!("{}", HolochainError::InstanceActive),
+            "instance is already active"
+        );
+        assert_eq!(
+            format!("{}", HolochainError::LoggingError),
+            "logging failed"
+        );
+        assert_eq!(
+            format!("{}", HolochainError::ZomeNotFound("bogozome".to_string())),
+            "zome not found: bogozome"
+        );
+        assert_eq!(
+            format!(
+                "{}",
+                HolochainError::InvalidDna("name is empty".to_string())
+            ),
+            "invalid dna: name is empty"
+        );
+        assert_eq!(
+            format!(
+                "{}",
+                HolochainError::ZomeFunctionFailed {
+                    zome: "test_zome".to_string(),
+                    function: "test_fn".to_string(),
+                    reason: "borked".to_string(),
+                }
+            ),
+            "test_fn in zome test_zome failed: borked"
+        );
+        assert_eq!(
+            format!(
+                "{}",
+                HolochainError::ValidationFailed("too long".to_string())
+            ),
+            "validation failed: too long"
+        );
+        assert_eq!(
+            format!("{}", HolochainError::QueueFull),
+            "action queue is full"
+        );
+        assert_eq!(
+            format!(
+                "{}",
+                HolochainError::ZomeFunctionNotFound {
+                    zome: "test_zome".to_string(),
+                    function: "bogusfn".to_string(),
+                }
+            ),
+            "function not found in zome test_zome: bogusfn"
+        );
+        assert_eq!(
+            format!(
+                "{}",
+                HolochainError::CapabilityDenied {
+                    zome: "test_zome".to_string(),
+                    function: "test_fn".to_string(),
+                }
+            ),
+            "capability denied for test_fn in zome test_zome"
+        );
+        assert_eq!(
+            format!("{}", HolochainError::EntryNotFound("Qmbogus".to_string())),
+            "entry not found: Qmbogus"
+        );
+        assert_eq!(
+            format!(
+                "{}",
+                HolochainError::DnaVersionMismatch {
+                    expected: "0.2.0".to_string(),
+                    found: "0.1.0".to_string(),
+                }
+            ),
+            "dna version mismatch: expected 0.2.0, found 0.1.0"
+        );
+        assert_eq!(format!("{}", HolochainError::Timeout), "timed out");
+        assert_eq!(
+            format!("{}", HolochainError::NotInitialized),
+            "instance is not initialized"
+        );
+        assert_eq!(
+            format!(
+                "{}",
+                HolochainError::SerializationError("expected value".to_string())
+            ),
+            "serialization failed: expected value"
+        );
+        assert_eq!(
+            format!("{}", HolochainError::IoError("file not found".to_string())),
+            "io failed: file not found"
+        );
+        assert_eq!(
+            format!("{}", HolochainError::Cancelled),
+            "call was cancelled"
+        );
+        assert_eq!(
+            format!(
+                "{}",
+                HolochainError::ActionRaceDetected {
+                    dispatched: 3,
+                    consumed: 2,
+                }
+            ),
+            "action race detected: dispatched action 3 but consumed 2"
+        );
+        assert_eq!(
+            format!(
+                "{}",
+                HolochainError::PayloadTooLarge {
+                    size: 11,
+                    limit: 10,
+                }
+            ),
+            "payload too large: 11 bytes is over the limit of 10"
+        );
+        assert_eq!(
+            format!(
+                "{}",
+                HolochainError::SnapshotVersionMismatch {
+                    expected: 1,
+                    found: 2,
+                }
+            ),
+            "snapshot version mismatch: expected 1, found 2"
+        );
+        assert_eq!(
+            format!("{}", HolochainError::WouldBlock),
+            "another thread is consuming actions"
+        );
+    }
+
+    #[test]
+    fn can_convert_dna_error() {
+        assert_eq!(
+            HolochainError::from(DnaError::Invalid("name is empty".to_string())),
+            HolochainError::InvalidDna("name is empty".to_string())
+        );
+        assert_eq!(
+            HolochainError::from(DnaError::Parse("bad json".to_string())),
+            HolochainError::SerializationError("could not parse dna: bad json".to_string())
+        );
+        assert_eq!(
+            HolochainError::from(DnaError::Io("missing.hcpkg".to_string())),
+            HolochainError::IoError("could not read dna: missing.hcpkg".to_string())
+        );
+    }
+
+    #[test]
+    fn can_convert_io_error() {
+        fn reads_missing_file() -> Result<String, HolochainError> {
+            Ok(::std::fs::read_to_string("/bogus/path/to/nothing")?)
+        }
+        match reads_missing_file() {
+            Err(HolochainError::IoError(err_msg)) => assert!(err_msg.contains("os error")),
+            _ => assert!(false),
+        };
+        assert_eq!(
+            HolochainError::from(io::Error::new(io::ErrorKind::PermissionDenied, "read only")),
+            HolochainError::IoError("read only".to_string())
+        );
+    }
+
+    #[test]
+    fn can_convert_serde_json_error() {
+        fn parses_bad_json() -> Result<serde_json::Value, HolochainError> {
+            Ok(serde_json::from_str("{bogus")?)
+        }
+        match parses_bad_json() {
+            Err(HolochainError::SerializationError(err_msg)) => assert!(!err_msg.is_empty()),
+            _ => assert!(false),
+        };
+    }
+
+    #[test]
+    fn can_box_as_error() {
+        fn boxes_hc_error() -> Result<(), Box<Error>> {
+            raises_hc_error(true)?;
+            Ok(())
+        }
+        match boxes_hc_error() {
+            Err(err) => assert_eq!(err.to_string(), "borked"),
+            Ok(_) => assert!(false),
+        };
+    }
 }
@@ -1,46 +1,604 @@
+use common::entry::Entry;
+use common::schema;
+use context::Context;
 use error::HolochainError;
+use futures::channel::oneshot;
+use hc_dna::hash;
+use logger::LogLevel;
+use metrics::Metrics;
+use nucleus::fncall::Call;
+use nucleus::ribosome::{self, NativeFunction, NativeValidator};
+use nucleus::signal::{self, Signal};
+use spawner::{Spawner, ThreadSpawner};
 use state::*;
-use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, TryLockError};
+use std::time::{Duration, Instant};
 
+/// the receiving end of a dispatched call, resolved once the call's result is consumed
+pub type CallResult = oneshot::Receiver<Result<String, HolochainError>>;
+
+type CallResultSender = oneshot::Sender<Result<String, HolochainError>>;
+
+/// run on every dispatched action, along with the state it is about to be reduced into,
+/// for cross-cutting concerns like metrics or auditing
+pub type Middleware = Arc<Fn(&Action, &State) + Send + Sync>;
+
+/// reduces an action into the state it leads to, in place of the built-in reducers
+pub type Reducer = fn(&State, &Action) -> State;
+
+/// identifies a dispatched action, increasing with each dispatch
+pub type ActionId = usize;
+
+// runs the function a call refers to, once the core has been unlocked
+type Execution = Box<FnOnce() -> Result<String, HolochainError> + Send>;
+
+// how far an action got while the core was locked
+enum Consumed {
+    // fully consumed
+    Done(ActionId),
+    // reduced, but the call's function is still to be run and its result stored
+    Call(ActionId, Call, Result<Execution, HolochainError>),
+}
+
+/// how many actions can be waiting to be consumed, unless set with `with_capacity`
+pub const DEFAULT_CAPACITY: usize = 1024;
+
+/// when an instance saves its state with its context's persister
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PersistencePolicy {
+    /// after each action is consumed, so that nothing is lost if the process dies
+    EveryAction,
+    /// once the instance is stopped, after the pending actions are consumed
+    OnStop,
+    /// only when `persist` is called
+    Manual,
+}
+
+/// a queue of actions and the state they are reduced into; clones of an instance
+/// share the same queue and state, so actions can be dispatched from any of them
+/// while they are consumed on the processing task
 #[derive(Clone)]
 pub struct Instance {
+    shared: Arc<Shared>,
+    processor: Arc<Mutex<Option<Processor>>>,
+}
+
+// what the processing task shares with the instance
+struct Shared {
+    core: Mutex<Core>,
+    // held while actions are consumed, so that they are consumed one at a time and in order
+    // without the core staying locked while a call's function runs
+    consuming: Mutex<()>,
+    // signalled when an action is dispatched or processing should stop
+    wakeup: Condvar,
+    // signalled when actions have been consumed, for those waiting for none to be pending
+    consumed: Condvar,
+    // kept outside the core so calls can be cancelled while another call is running
+    cancelled: Arc<Mutex<HashSet<usize>>>,
+}
+
+struct Core {
     state: State,
     pending_actions: VecDeque<Action>,
+    capacity: usize,
+    next_action_id: ActionId,
+    history: Vec<Action>,
+    functions: HashMap<(String, String), NativeFunction>,
+    validators: HashMap<String, NativeValidator>,
+    middleware: Vec<Middleware>,
+    result_senders: HashMap<usize, CallResultSender>,
+    // the ids of calls whose results are to be discarded
+    cancelled: Arc<Mutex<HashSet<usize>>>,
+    observers: Vec<Sender<State>>,
+    signal_receivers: Vec<Sender<Signal>>,
+    context: Option<Arc<Context>>,
+    persistence: PersistencePolicy,
+    processing: bool,
+    metrics: Metrics,
+    reducer: Option<Reducer>,
+    // whether a call's function is running, so that the call is consumed but not finished
+    running_call: bool,
 }
 
-impl Instance {
-    pub fn dispatch(&mut self, action: Action) {
-        self.pending_actions.push_back(action);
+// a running processing task, which is stopped when this is dropped
+struct Processor {
+    shared: Arc<Shared>,
+    // disconnected without a message if the task panicked
+    finished: Option<Receiver<()>>,
+}
+
+impl Shared {
+    // a function that panicked while the core was locked can't have left it half reduced,
+    // as the state is only replaced once an action has been fully reduced
+    fn lock(&self) -> MutexGuard<'_, Core> {
+        self.core
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
     }
 
-    pub fn pending_actions(&self) -> &VecDeque<Action> {
-        &self.pending_actions
+    // the consuming lock guards no data, so there is nothing a panic could have left broken
+    fn lock_consuming(&self) -> MutexGuard<'_, ()> {
+        self.consuming
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
     }
 
-    pub fn consume_next_action(&mut self) -> Result<(), HolochainError> {
-        if !self.pending_actions.is_empty() {
-            let result = self.pending_actions.pop_front();
-            match result {
-                None => {
-                    return Err(HolochainError::ErrorGeneric(
-                        "nothing to consume".to_string(),
-                    ))
+    // consumes the oldest pending action, running a call's function with the core unlocked
+    // so that the state can be read and actions dispatched meanwhile, e.g. by callers that
+    // gave up waiting for it; callers must hold the consuming lock
+    fn consume_next_action(&self) -> Result<Option<ActionId>, HolochainError> {
+        let begun = self.lock().begin_next_action();
+        let result = match begun {
+            Ok(Some(Consumed::Call(id, call, execution))) => {
+                // signals left over from functions run outside of a call aren't this call's
+                signal::take_emitted();
+                let started = Instant::now();
+                let result = execution.and_then(|execute| execute());
+                let elapsed = started.elapsed();
+                let signals = signal::take_emitted();
+                match self.lock().finish_call(&call, result, elapsed, signals) {
+                    Some(err) => Err(err),
+                    None => Ok(Some(id)),
                 }
-                Some(action) => self.state = self.state.clone().reduce(&action),
+            }
+            Ok(Some(Consumed::Done(id))) => Ok(Some(id)),
+            Ok(None) => Ok(None),
+            Err(err) => Err(err),
+        };
+        self.consumed.notify_all();
+        result
+    }
+
+    // consumes pending actions up to and including the dispatched one, returning its result;
+    // the action consumed last must be the dispatched one, or its result would be another's;
+    // callers must hold the consuming lock
+    fn dispatch_and_consume(&self, action: Action) -> Result<ActionId, HolochainError> {
+        let id = self.lock().dispatch(action)?;
+        loop {
+            let next_id = self.lock().next_pending_id();
+            let result = self.consume_next_action();
+            if next_id >= id {
+                return match result? {
+                    Some(consumed) if consumed == id => Ok(id),
+                    consumed => Err(HolochainError::ActionRaceDetected {
+                        dispatched: id,
+                        consumed: consumed.unwrap_or(next_id),
+                    }),
+                };
+            }
+        }
+    }
+}
+
+impl Processor {
+    // tells the task to stop once it has finished the action it is consuming, and waits for it
+    fn stop(&mut self) -> Result<(), HolochainError> {
+        self.shared.lock().processing = false;
+        self.shared.wakeup.notify_all();
+        match self.finished.take() {
+            Some(finished) => finished
+                .recv()
+                .map_err(|_| HolochainError::new("the processing task panicked")),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for Processor {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+// nothing can panic while the cancelled calls are locked, but don't rely on it
+fn lock_cancelled(cancelled: &Mutex<HashSet<usize>>) -> MutexGuard<'_, HashSet<usize>> {
+    cancelled
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+// consumes actions as they are dispatched, until told to stop
+fn process(shared: &Shared) {
+    loop {
+        {
+            let mut core = shared.lock();
+            while core.processing && core.pending_actions.is_empty() {
+                core = shared
+                    .wakeup
+                    .wait(core)
+                    .unwrap_or_else(|poisoned| poisoned.into_inner());
+            }
+            if !core.processing {
+                return;
             }
         }
+        // failing calls are stored in state as the calls' results, as with drain
+        let _consuming = shared.lock_consuming();
+        let _ = shared.consume_next_action();
+    }
+}
+
+impl Instance {
+    /// queue an action to be consumed, failing if the queue is full
+    /// so that callers can back off until some actions have been consumed
+    pub fn dispatch(&mut self, action: Action) -> Result<ActionId, HolochainError> {
+        let id = self.shared.lock().dispatch(action)?;
+        self.shared.wakeup.notify_all();
+        Ok(id)
+    }
+
+    /// dispatch a call, returning a receiver for the call's result
+    pub fn dispatch_call(&mut self, call: Call) -> Result<CallResult, HolochainError> {
+        let (sender, receiver) = oneshot::channel();
+        {
+            let mut core = self.shared.lock();
+            let call_id = call.id();
+            core.dispatch(Action::Nucleus(::nucleus::Action::Call(call)))?;
+            core.result_senders.insert(call_id, sender);
+        }
+        self.shared.wakeup.notify_all();
+        Ok(receiver)
+    }
+
+    /// cancel a dispatched call, so that it isn't run if it hasn't been consumed yet
+    /// and its result is discarded rather than stored in the state if it has;
+    /// whoever is waiting on its result is told it was dropped
+    pub fn cancel_call(&self, call_id: usize) {
+        lock_cancelled(&self.shared.cancelled).insert(call_id);
+    }
+
+    /// dispatch an action and consume it straight away, after any actions dispatched before it,
+    /// returning how consuming this action went (failures of the earlier actions are stored
+    /// in state, as with drain)
+    pub fn dispatch_and_consume(&mut self, action: Action) -> Result<ActionId, HolochainError> {
+        let _consuming = self.shared.lock_consuming();
+        self.shared.dispatch_and_consume(action)
+    }
+
+    /// dispatch a call and consume it straight away, as dispatch_and_consume does,
+    /// taking its result rather than leaving it in the state
+    pub fn dispatch_and_take(
+        &mut self,
+        call: Call,
+    ) -> Result<Result<String, HolochainError>, HolochainError> {
+        let _consuming = self.shared.lock_consuming();
+        let (sender, mut receiver) = oneshot::channel();
+        let call_id = call.id();
+        self.shared.lock().result_senders.insert(call_id, sender);
+        let consumed = self
+            .shared
+            .dispatch_and_consume(Action::Nucleus(::nucleus::Action::Call(call)));
+        self.shared.lock().result_senders.remove(&call_id);
+        consumed?;
+        match receiver.try_recv() {
+            Ok(Some(result)) => Ok(result),
+            _ => Err(HolochainError::new("function call returned no result")),
+        }
+    }
+
+    /// rebuild a state from recorded actions, e.g. another instance's history, returning
+    /// it on a fresh instance like `fresh` gives, so this instance is unchanged; calls
+    /// aren't run again, as the history records their results and signals, and nothing
+    /// is sent to receivers, observers or middleware, nor saved
+    pub fn replay(&self, actions: &[Action]) -> Result<Instance, HolochainError> {
+        let replayed = self.fresh();
+        {
+            let mut core = replayed.shared.lock();
+            for action in actions {
+                core.admit(action)?;
+                core.reduce(action.clone());
+            }
+        }
+        Ok(replayed)
+    }
+
+    /// the actions waiting to be consumed, oldest first
+    pub fn pending_actions(&self) -> VecDeque<Action> {
+        self.shared.lock().pending_actions.clone()
+    }
+
+    /// consume the oldest pending action, returning its id (or None if nothing was pending)
+    pub fn consume_next_action(&mut self) -> Result<Option<ActionId>, HolochainError> {
+        let _consuming = self.shared.lock_consuming();
+        self.shared.consume_next_action()
+    }
+
+    /// consume the oldest pending action like `consume_next_action`, but without waiting:
+    /// returns None straight away if nothing is pending, or a WouldBlock error if another
+    /// thread is busy consuming, so a container can consume actions from its own event loop
+    pub fn try_consume_next_action(&mut self) -> Result<Option<ActionId>, HolochainError> {
+        let _consuming = match self.shared.consuming.try_lock() {
+            Ok(consuming) => consuming,
+            Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner(),
+            Err(TryLockError::WouldBlock) => return Err(HolochainError::WouldBlock),
+        };
+        self.shared.consume_next_action()
+    }
+
+    /// consume every pending action; failing calls don't stop the draining,
+    /// as their failures are stored in state as the calls' results
+    pub fn drain(&mut self) {
+        let _consuming = self.shared.lock_consuming();
+        while !self.shared.lock().pending_actions.is_empty() {
+            let _ = self.shared.consume_next_action();
+        }
+    }
+
+    /// wait until no actions are pending, returning false if some still are once timeout
+    /// has passed; actions are only consumed meanwhile if something is consuming them,
+    /// e.g. the processing task
+    pub fn wait_for_idle(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        let mut core = self.shared.lock();
+        while !core.pending_actions.is_empty() || core.running_call {
+            let now = Instant::now();
+            if now >= deadline {
+                return false;
+            }
+            core = match self.shared.consumed.wait_timeout(core, deadline - now) {
+                Ok((core, _)) => core,
+                Err(poisoned) => poisoned.into_inner().0,
+            };
+        }
+        true
+    }
+
+    /// start consuming actions on a thread of their own as they are dispatched,
+    /// so that nobody has to call `consume_next_action`
+    pub fn start_processing(&mut self) -> Result<(), HolochainError> {
+        self.start_processing_with(&ThreadSpawner)
+    }
+
+    /// start consuming actions as they are dispatched on a task run by spawner,
+    /// for embedders that want the instance to run on their own runtime
+    pub fn start_processing_with(&mut self, spawner: &Spawner) -> Result<(), HolochainError> {
+        let mut processor = self
+            .processor
+            .lock()
+            .map_err(|_| HolochainError::new("could not lock the processor"))?;
+        if processor.is_some() {
+            return Err(HolochainError::new("instance is already processing"));
+        }
+        self.shared.lock().processing = true;
+        let shared = self.shared.clone();
+        let (sender, receiver) = channel();
+        spawner.spawn(Box::new(move || {
+            process(&shared);
+            let _ = sender.send(());
+        }));
+        *processor = Some(Processor {
+            shared: self.shared.clone(),
+            finished: Some(receiver),
+        });
         Ok(())
     }
 
+    /// stop the processing task once it has consumed the action it is on, waiting for it
+    /// to finish; actions still pending are left for `drain` or the next `start_processing`
+    pub fn stop_processing(&mut self) -> Result<(), HolochainError> {
+        let processor = self
+            .processor
+            .lock()
+            .map_err(|_| HolochainError::new("could not lock the processor"))?
+            .take();
+        match processor {
+            Some(mut processor) => processor.stop(),
+            None => Ok(()),
+        }
+    }
+
+    /// whether actions are being consumed on the processing task
+    pub fn processing(&self) -> bool {
+        self.shared.lock().processing
+    }
+
+    /// subscribe to the state, which is sent each time an action has been consumed
+    pub fn subscribe(&mut self) -> Receiver<State> {
+        let (sender, receiver) = channel();
+        self.shared.lock().observers.push(sender);
+        receiver
+    }
+
+    /// receive the signals emitted by functions, each sent once the result of the call
+    /// that emitted it has been stored
+    pub fn subscribe_signals(&mut self) -> Receiver<Signal> {
+        let (sender, receiver) = channel();
+        self.shared.lock().signal_receivers.push(sender);
+        receiver
+    }
+
+    /// set the context, whose persister the state is saved with as the persistence policy says
+    pub fn set_context(&mut self, context: Arc<Context>) {
+        self.shared.lock().context = Some(context);
+    }
+
+    /// turn saving the state after each action on or off (it's on by default);
+    /// turning it off leaves saving to `persist`
+    pub fn set_autosave(&mut self, autosave: bool) {
+        self.set_persistence_policy(if autosave {
+            PersistencePolicy::EveryAction
+        } else {
+            PersistencePolicy::Manual
+        });
+    }
+
+    /// set when the state is saved (after every action by default)
+    pub fn set_persistence_policy(&mut self, policy: PersistencePolicy) {
+        self.shared.lock().persistence = policy;
+    }
+
+    pub fn persistence_policy(&self) -> PersistencePolicy {
+        self.shared.lock().persistence
+    }
+
+    /// save the state with the context's persister now, whatever the persistence policy,
+    /// failing if there is no context or the persister fails
+    pub fn persist(&self) -> Result<(), HolochainError> {
+        self.shared.lock().persist()
+    }
+
+    /// register a native function to be run when a call to `fn_name` in `zome_name` is consumed
+    pub fn register_function<F>(&mut self, zome_name: &str, fn_name: &str, function: F)
+    where
+        F: Fn(&str) -> Result<String, HolochainError> + Send + Sync + 'static,
+    {
+        self.shared.lock().functions.insert(
+            (zome_name.to_string(), fn_name.to_string()),
+            Arc::new(function),
+        );
+    }
+
+    /// register a native validator to be run on commits of entries of `entry_type`
+    pub fn register_validator<F>(&mut self, entry_type: &str, validator: F)
+    where
+        F: Fn(&Entry) -> Result<(), String> + Send + Sync + 'static,
+    {
+        self.shared
+            .lock()
+            .validators
+            .insert(entry_type.to_string(), Arc::new(validator));
+    }
+
+    /// add middleware to be run on each dispatched action before it reaches the reducer,
+    /// after any middleware added before it
+    pub fn add_middleware<F>(&mut self, middleware: F)
+    where
+        F: Fn(&Action, &State) + Send + Sync + 'static,
+    {
+        self.shared.lock().middleware.push(Arc::new(middleware));
+    }
+
     pub fn new() -> Self {
-        Instance {
+        Instance::with_capacity(DEFAULT_CAPACITY)
+    }
+
+    /// create an instance that queues at most capacity actions
+    pub fn with_capacity(capacity: usize) -> Self {
+        Instance::from_core(Core {
             state: State::new(),
             pending_actions: VecDeque::new(),
+            capacity,
+            next_action_id: 0,
+            history: Vec::new(),
+            functions: HashMap::new(),
+            validators: HashMap::new(),
+            middleware: Vec::new(),
+            result_senders: HashMap::new(),
+            cancelled: Arc::new(Mutex::new(HashSet::new())),
+            observers: Vec::new(),
+            signal_receivers: Vec::new(),
+            context: None,
+            persistence: PersistencePolicy::EveryAction,
+            processing: false,
+            metrics: Metrics::new(),
+            reducer: None,
+            running_call: false,
+        })
+    }
+
+    /// create an instance that reduces actions with reducer rather than the built-in
+    /// reducers, e.g. to test how new actions are handled in isolation
+    pub fn with_reducer(reducer: Reducer) -> Self {
+        let instance = Instance::new();
+        instance.shared.lock().reducer = Some(reducer);
+        instance
+    }
+
+    fn from_core(core: Core) -> Self {
+        Instance {
+            shared: Arc::new(Shared {
+                cancelled: core.cancelled.clone(),
+                core: Mutex::new(core),
+                consuming: Mutex::new(()),
+                wakeup: Condvar::new(),
+                consumed: Condvar::new(),
+            }),
+            processor: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// create an instance that resumes from a previously saved state
+    pub fn from_state(state: State) -> Self {
+        let instance = Instance::new();
+        instance.shared.lock().state = state;
+        instance
+    }
+
+    /// create an instance with a fresh state that keeps this one's registered
+    /// functions, validators, middleware, observers, signal receivers, context and reducer
+    /// (but isn't processing yet)
+    pub fn fresh(&self) -> Self {
+        let core = self.shared.lock();
+        let instance = Instance::with_capacity(core.capacity);
+        {
+            let mut fresh = instance.shared.lock();
+            fresh.functions = core.functions.clone();
+            fresh.validators = core.validators.clone();
+            fresh.middleware = core.middleware.clone();
+            fresh.observers = core.observers.clone();
+            fresh.signal_receivers = core.signal_receivers.clone();
+            fresh.context = core.context.clone();
+            fresh.persistence = core.persistence;
+            fresh.reducer = core.reducer;
+        }
+        instance
+    }
+
+    /// take other's state and history in place of this instance's own, e.g. once a fresh
+    /// instance has been initialized, keeping everything else; unlike assigning other to
+    /// a handle, every clone of this instance sees the change
+    pub fn adopt(&mut self, other: &Instance) {
+        let (state, history) = {
+            let other = other.shared.lock();
+            (other.state.clone(), other.history.clone())
+        };
+        let _consuming = self.shared.lock_consuming();
+        let mut core = self.shared.lock();
+        core.state = state;
+        core.history = history;
+        core.notify_observers();
+        core.save();
+    }
+
+    /// consume action against a copy of the current state with this instance's functions
+    /// and validators, returning the state it would lead to; this instance is unchanged,
+    /// and nothing is saved, stored, observed or run through middleware
+    pub fn dry_run(&self, action: Action) -> Result<State, HolochainError> {
+        let mut scratch = Instance::new();
+        {
+            let core = self.shared.lock();
+            let mut scratch = scratch.shared.lock();
+            scratch.state = core.state.clone();
+            scratch.functions = core.functions.clone();
+            scratch.validators = core.validators.clone();
+            scratch.reducer = core.reducer;
         }
+        scratch.dispatch_and_consume(action)?;
+        Ok(scratch.state())
+    }
+
+    /// the current state (cheap to copy, as states share their unchanged parts)
+    pub fn state(&self) -> State {
+        self.shared.lock().state.clone()
+    }
+
+    /// every action that has been applied to the state, in the order it was applied
+    pub fn history(&self) -> Vec<Action> {
+        self.shared.lock().history.clone()
     }
 
-    pub fn state(&self) -> &State {
-        &self.state
+    /// the counters kept about the calls this instance has consumed
+    pub fn metrics(&self) -> Metrics {
+        self.shared.lock().metrics.clone()
+    }
+
+    /// the address of the current state's json, for cheaply telling whether two states
+    /// are equal; instances with the same state have the same hash
+    pub fn state_hash(&self) -> String {
+        // states are plain data, so serializing them can't fail
+        hash::address(&self.state().to_json().unwrap())
     }
 }
 
@@ -49,3 +607,259 @@ impl Default for Instance {
         Self::new()
     }
 }
+
+impl Core {
+    fn dispatch(&mut self, action: Action) -> Result<ActionId, HolochainError> {
+        if self.pending_actions.len() >= self.capacity {
+            return Err(HolochainError::QueueFull);
+        }
+        let id = self.next_action_id;
+        self.next_action_id += 1;
+        self.pending_actions.push_back(action);
+        Ok(id)
+    }
+
+    // the id of the oldest pending action; actions are consumed in the order they were
+    // dispatched, so every action after it has the next higher id
+    fn next_pending_id(&self) -> ActionId {
+        self.next_action_id - self.pending_actions.len()
+    }
+
+    // consumes the oldest pending action as far as can be done with the core locked,
+    // leaving a call's function to be run once it is unlocked
+    fn begin_next_action(&mut self) -> Result<Option<Consumed>, HolochainError> {
+        let id = self.next_pending_id();
+        let action = match self.pending_actions.pop_front() {
+            Some(action) => action,
+            None => return Ok(None),
+        };
+        for middleware in &self.middleware {
+            middleware(&action, &self.state);
+        }
+        self.admit(&action)?;
+        self.reduce(action.clone());
+        // cancelled calls aren't run
+        if let Action::Nucleus(::nucleus::Action::Call(ref call)) = action {
+            if !self.take_cancelled(call) {
+                self.running_call = true;
+                return Ok(Some(Consumed::Call(id, call.clone(), self.execution(call))));
+            }
+        }
+        self.notify_observers();
+        self.save();
+        Ok(Some(Consumed::Done(id)))
+    }
+
+    // checks that action may be reduced, storing the entries it commits; rejected entries
+    // and links never make it into the state or the entry store
+    fn admit(&self, action: &Action) -> Result<(), HolochainError> {
+        match *action {
+            Action::Agent(::agent::Action::Commit(ref entry))
+            | Action::Agent(::agent::Action::SignedCommit(ref entry, _)) => {
+                self.validate(entry)?;
+                self.store(entry)?;
+            }
+            Action::Agent(::agent::Action::LinkEntries {
+                ref base,
+                ref target,
+                ..
+            }) => {
+                self.validate_link(base, target)?;
+            }
+            Action::Agent(::agent::Action::UpdateEntry {
+                ref old_address,
+                ref entry,
+                ..
+            }) => {
+                self.validate_exists(old_address)?;
+                self.validate(entry)?;
+                self.store(entry)?;
+            }
+            Action::Agent(::agent::Action::RemoveEntry(ref address)) => {
+                self.validate_exists(address)?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    // stores the result of a call whose function has run, then the signals it emitted,
+    // returning the failure to report if it failed; calls cancelled while running
+    // have their result and signals discarded
+    fn finish_call(
+        &mut self,
+        call: &Call,
+        result: Result<String, HolochainError>,
+        elapsed: Duration,
+        signals: Vec<Signal>,
+    ) -> Option<HolochainError> {
+        self.running_call = false;
+        self.metrics.record_call(elapsed, result.is_err());
+        let failure = if self.take_cancelled(call) {
+            None
+        } else {
+            self.store_result(call, result, signals)
+        };
+        self.notify_observers();
+        self.save();
+        failure
+    }
+
+    // sends the current state to every observer, forgetting those that have gone away
+    fn notify_observers(&mut self) {
+        let state = &self.state;
+        self.observers
+            .retain(|observer| observer.send(state.clone()).is_ok());
+    }
+
+    // saves the state after an action if the policy says to, logging rather than
+    // returning failures so that a broken persister doesn't stop the instance
+    fn save(&self) {
+        let context = match self.context {
+            Some(ref context) if self.persistence == PersistencePolicy::EveryAction => context,
+            _ => return,
+        };
+        if let Err(err) = self.persist() {
+            let _ = context.log_at(LogLevel::Error, &format!("could not save state: {}", err));
+        }
+    }
+
+    fn persist(&self) -> Result<(), HolochainError> {
+        let context = self
+            .context
+            .as_ref()
+            .ok_or_else(|| HolochainError::new("there is no context to persist with"))?;
+        context.lock_persister().save(&self.state)
+    }
+
+    // applies an action to the state, recording it in the history
+    fn reduce(&mut self, action: Action) {
+        self.state = match self.reducer {
+            Some(reducer) => reducer(&self.state, &action),
+            None => self.state.clone().reduce(&action),
+        };
+        self.history.push(action);
+    }
+
+    // entries must conform to their type's schema and pass its validator,
+    // where the dna gives a schema and a validator has been registered
+    fn validate(&self, entry: &Entry) -> Result<(), HolochainError> {
+        if let Some(schema) = self.state.nucleus().entry_schema(&entry.entry_type()) {
+            schema::validate(&schema, &entry.content())?;
+        }
+        match self.validators.get(&entry.entry_type()) {
+            Some(validator) => validator(entry).map_err(HolochainError::ValidationFailed),
+            None => Ok(()),
+        }
+    }
+
+    // puts a valid entry in the context's entry store and publishes it to the context's dht,
+    // if there is a context
+    fn store(&self, entry: &Entry) -> Result<(), HolochainError> {
+        let context = match self.context {
+            Some(ref context) => context,
+            None => return Ok(()),
+        };
+        context.lock_entry_store().put(entry)?;
+        match context.lock_dht() {
+            Some(mut dht) => dht.publish(&entry.address(), entry),
+            None => Ok(()),
+        }
+    }
+
+    // links can only be made between entries that have been committed
+    fn validate_link(&self, base: &str, target: &str) -> Result<(), HolochainError> {
+        self.validate_exists(base)?;
+        self.validate_exists(target)
+    }
+
+    // only entries that have been committed and not removed can be linked, updated or removed
+    fn validate_exists(&self, address: &str) -> Result<(), HolochainError> {
+        if self.state.agent().has_entry(address) {
+            Ok(())
+        } else {
+            Err(HolochainError::EntryNotFound(address.to_string()))
+        }
+    }
+
+    // stores a call's result in the state and sends it to whoever is waiting for it,
+    // who takes it out of the state again, then does the same for the signals the call emitted
+    fn store_result(
+        &mut self,
+        call: &Call,
+        result: Result<String, HolochainError>,
+        signals: Vec<Signal>,
+    ) -> Option<HolochainError> {
+        let result_action = Action::Nucleus(::nucleus::Action::ReturnResult(
+            call.clone(),
+            result.clone(),
+        ));
+        self.reduce(result_action);
+        if self.send_result(call, result.clone()) {
+            self.reduce(Action::Nucleus(::nucleus::Action::TakeResult(call.clone())));
+        }
+        for signal in signals {
+            self.reduce(Action::Nucleus(::nucleus::Action::EmitSignal(
+                signal.clone(),
+            )));
+            self.signal_receivers
+                .retain(|receiver| receiver.send(signal.clone()).is_ok());
+        }
+        match result {
+            Err(err @ HolochainError::ZomeFunctionFailed { .. }) => Some(err),
+            _ => None,
+        }
+    }
+
+    // whether call has been cancelled, in which case its result sender is dropped
+    fn take_cancelled(&mut self, call: &Call) -> bool {
+        if !lock_cancelled(&self.cancelled).remove(&call.id()) {
+            return false;
+        }
+        self.result_senders.remove(&call.id());
+        true
+    }
+
+    // resolves the receiver handed out by dispatch_call, if there is one,
+    // returning whether it was still waiting
+    fn send_result(&mut self, call: &Call, result: Result<String, HolochainError>) -> bool {
+        match self.result_senders.remove(&call.id()) {
+            // the receiver may have been dropped, in which case nobody is waiting
+            Some(sender) => sender.send(result).is_ok(),
+            None => false,
+        }
+    }
+
+    // what runs the function a call refers to, producing the result to be stored in state,
+    // with errors raised by the function itself labelled with the call they came from
+    fn execution(&self, call: &Call) -> Result<Execution, HolochainError> {
+        // the reducer has already given calls it rejected, e.g. to unknown zomes, their result
+        if let Some(Err(err)) = self.state.nucleus().call_result(call) {
+            return Err(err);
+        }
+        let (zome, function, params) = (call.zome_name(), call.fn_name(), call.parameters());
+        let label = move |err: HolochainError| HolochainError::ZomeFunctionFailed {
+            zome,
+            function,
+            reason: err.to_string(),
+        };
+        // native functions stand in for the zome's own code
+        if let Some(native) = self.functions.get(&(call.zome_name(), call.fn_name())) {
+            let native = native.clone();
+            return Ok(Box::new(move || native(&params).map_err(label)));
+        }
+        match self
+            .state
+            .nucleus()
+            .zome_function_code(&call.zome_name(), &call.fn_name())
+        {
+            Some(code) => {
+                let fn_name = call.fn_name();
+                Ok(Box::new(move || {
+                    ribosome::call(&code, &fn_name, &params).map_err(label)
+                }))
+            }
+            None => Err(HolochainError::NotImplemented),
+        }
+    }
+}
@@ -1,42 +1,91 @@
 #![deny(warnings)]
+extern crate futures;
+extern crate hc_agent;
 extern crate hc_dna;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+extern crate wasmi;
 pub mod agent;
 pub mod common;
 pub mod context;
+pub mod entry_store;
 pub mod error;
 pub mod instance;
 pub mod logger;
+pub mod metrics;
 pub mod network;
 pub mod nucleus;
 pub mod persister;
 pub mod source_chain;
+pub mod spawner;
 pub mod state;
+#[cfg(any(test, feature = "test_utils"))]
+pub mod test_utils;
 
 #[cfg(test)]
 mod tests {
     use agent::Action::*;
     use hc_dna::Dna;
     use instance::Instance;
+    use logger::Logger;
     use nucleus::Action::*;
     use state::Action::*;
+    use std::sync::{Arc, Mutex};
+    use test_utils::SpyPersister;
 
     #[test]
     fn adding_messages_to_queue() {
         let mut instance = Instance::new();
 
         let dna = Dna::new();
-        instance.dispatch(Nucleus(InitApplication(dna.clone())));
+        instance
+            .dispatch(Nucleus(InitApplication(dna.clone())))
+            .unwrap();
         assert_eq!(
             *instance.pending_actions().back().unwrap(),
             Nucleus(InitApplication(dna.clone()))
         );
 
-        let entry = ::common::entry::Entry::new(&String::new());
+        let entry = ::common::entry::Entry::new("test_type", "");
         let action = Agent(Commit(entry));
-        instance.dispatch(action.clone());
+        instance.dispatch(action.clone()).unwrap();
         assert_eq!(*instance.pending_actions().back().unwrap(), action);
     }
 
+    #[test]
+    fn dispatching_and_consuming_report_action_ids() {
+        let mut instance = Instance::new();
+        assert_eq!(instance.consume_next_action(), Ok(None));
+
+        let entry = ::common::entry::Entry::new("test_type", "");
+        let first = instance.dispatch(Agent(Commit(entry.clone()))).unwrap();
+        let second = instance.dispatch(Agent(Commit(entry))).unwrap();
+        assert!(first < second);
+
+        assert_eq!(instance.consume_next_action(), Ok(Some(first)));
+        assert_eq!(instance.consume_next_action(), Ok(Some(second)));
+        assert_eq!(instance.consume_next_action(), Ok(None));
+    }
+
+    #[test]
+    fn dispatching_to_full_queue_fails() {
+        let mut instance = Instance::with_capacity(2);
+        let entry = ::common::entry::Entry::new("test_type", "");
+        instance.dispatch(Agent(Commit(entry.clone()))).unwrap();
+        instance.dispatch(Agent(Commit(entry.clone()))).unwrap();
+        assert_eq!(
+            instance.dispatch(Agent(Commit(entry.clone()))),
+            Err(::error::HolochainError::QueueFull)
+        );
+        assert_eq!(instance.pending_actions().len(), 2);
+
+        // consuming makes room again
+        instance.consume_next_action().unwrap();
+        assert!(instance.dispatch(Agent(Commit(entry))).is_ok());
+    }
+
     #[test]
     fn consuming_actions_and_checking_state_mutation() {
         let mut instance = Instance::new();
@@ -45,22 +94,636 @@ mod tests {
 
         let dna = Dna::new();
         let action = Nucleus(InitApplication(dna.clone()));
-        instance.dispatch(action.clone());
+        instance.dispatch(action.clone()).unwrap();
 
         match instance.consume_next_action() {
-            Ok(()) => assert!(true),
+            Ok(Some(_)) => assert!(true),
+            Ok(None) => assert!(false),
             Err(_) => assert!(false),
         };
 
         assert_eq!(instance.state().nucleus().dna(), Some(dna));
         assert_eq!(instance.state().nucleus().initialized(), true);
 
-        instance.dispatch(action.clone());
+        instance.dispatch(action.clone()).unwrap();
         match instance.consume_next_action() {
-            Ok(()) => assert!(true),
+            Ok(Some(_)) => assert!(true),
+            Ok(None) => assert!(false),
             Err(_) => assert!(false),
         };
 
         assert_eq!(instance.state().nucleus().initialized(), true);
     }
+
+    // an instance initialized with a dna containing a single zome called "test_zome"
+    fn test_instance() -> Instance {
+        let mut instance = Instance::new();
+        let mut dna = Dna::new();
+        let mut zome = ::hc_dna::zome::Zome::new();
+        zome.name = "test_zome".to_string();
+        dna.zomes.push(zome);
+        instance.dispatch(Nucleus(InitApplication(dna))).unwrap();
+        instance.consume_next_action().unwrap();
+        instance
+    }
+
+    #[test]
+    fn dispatching_call_with_parameters() {
+        let mut instance = Instance::new();
+
+        let params = r#"{"name":"bob","count":3}"#;
+        let call = ::nucleus::fncall::Call::new("test_zome", "some_fn").with_parameters(params);
+        instance.dispatch(Nucleus(Call(call))).unwrap();
+
+        match *instance.pending_actions().back().unwrap() {
+            Nucleus(Call(ref call)) => {
+                assert_eq!(call.zome_name(), "test_zome");
+                assert_eq!(call.fn_name(), "some_fn");
+                assert_eq!(call.parameters(), params);
+            }
+            _ => assert!(false),
+        };
+
+        match instance.consume_next_action() {
+            Ok(Some(_)) => assert!(true),
+            Ok(None) => assert!(false),
+            Err(_) => assert!(false),
+        };
+    }
+
+    #[test]
+    fn consuming_call_stores_function_result() {
+        let mut instance = test_instance();
+        instance.register_function("test_zome", "greet", |params| {
+            Ok(format!("hello {}", params))
+        });
+
+        let call = ::nucleus::fncall::Call::new("test_zome", "greet").with_parameters("bob");
+        instance.dispatch(Nucleus(Call(call.clone()))).unwrap();
+        instance.consume_next_action().unwrap();
+        assert_eq!(
+            instance.state().nucleus().call_result(&call),
+            Some(Ok("hello bob".to_string()))
+        );
+
+        // calls to functions that don't exist yet are not implemented
+        let call = ::nucleus::fncall::Call::new("test_zome", "bogusfn");
+        instance.dispatch(Nucleus(Call(call.clone()))).unwrap();
+        instance.consume_next_action().unwrap();
+        assert_eq!(
+            instance.state().nucleus().call_result(&call),
+            Some(Err(::error::HolochainError::NotImplemented))
+        );
+    }
+
+    #[test]
+    fn consuming_call_runs_zome_wasm() {
+        let mut instance = Instance::new();
+        let mut dna = Dna::new();
+        let mut zome = ::hc_dna::zome::ZomeBuilder::new()
+            .with_function("main", "echo")
+            .build();
+        zome.capabilities[0].code.code = include_bytes!("../test-fixtures/echo.wasm").to_vec();
+        dna.add_zome("test_zome", zome).unwrap();
+        instance.dispatch(Nucleus(InitApplication(dna))).unwrap();
+        instance.consume_next_action().unwrap();
+
+        let call =
+            ::nucleus::fncall::Call::new("test_zome", "echo").with_parameters(r#"{"name":"bob"}"#);
+        instance.dispatch(Nucleus(Call(call.clone()))).unwrap();
+        instance.consume_next_action().unwrap();
+        assert_eq!(
+            instance.state().nucleus().call_result(&call),
+            Some(Ok(r#"{"name":"bob"}"#.to_string()))
+        );
+    }
+
+    #[test]
+    fn state_hash_follows_state() {
+        let mut instance = test_instance();
+        let initial_hash = instance.state_hash();
+        assert_eq!(instance.state_hash(), initial_hash);
+
+        let entry = ::common::entry::Entry::new("test_type", "some content");
+        instance
+            .dispatch_and_consume(Agent(Commit(entry.clone())))
+            .unwrap();
+        let hash = instance.state_hash();
+        assert_ne!(hash, initial_hash);
+
+        let replayed = Instance::new().replay(&instance.history()).unwrap();
+        assert_eq!(replayed.state_hash(), hash);
+
+        // committing the same entry again changes nothing
+        instance.dispatch_and_consume(Agent(Commit(entry))).unwrap();
+        assert_eq!(instance.state_hash(), hash);
+    }
+
+    #[test]
+    fn custom_reducer_runs_on_dispatch() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        static REDUCED: AtomicBool = AtomicBool::new(false);
+        fn sentinel_reducer(state: &::state::State, _: &::state::Action) -> ::state::State {
+            REDUCED.store(true, Ordering::SeqCst);
+            state.clone()
+        }
+
+        let mut instance = Instance::with_reducer(sentinel_reducer);
+        let entry = ::common::entry::Entry::new("test_type", "some content");
+        instance.dispatch(Agent(Commit(entry.clone()))).unwrap();
+        assert!(!REDUCED.load(Ordering::SeqCst));
+
+        instance.consume_next_action().unwrap();
+        assert!(REDUCED.load(Ordering::SeqCst));
+        // the custom reducer is used instead of the built-in ones
+        assert!(!instance.state().agent().has_entry(&entry.address()));
+        assert_eq!(instance.history().len(), 1);
+    }
+
+    #[test]
+    fn replaying_history_rebuilds_state() {
+        let mut instance = test_instance();
+        instance.register_function("test_zome", "greet", |params| {
+            Ok(format!("hello {}", params))
+        });
+        instance.register_function("test_zome", "fail", |_| {
+            Err(::error::HolochainError::new("borked"))
+        });
+        let entry = ::common::entry::Entry::new("test_type", "some content");
+        for action in &[
+            Agent(Commit(entry.clone())),
+            Nucleus(Call(
+                ::nucleus::fncall::Call::new("test_zome", "greet").with_parameters("bob"),
+            )),
+            Nucleus(Call(::nucleus::fncall::Call::new("test_zome", "fail"))),
+        ] {
+            let _ = instance.dispatch_and_consume(action.clone());
+        }
+
+        // recorded results are replayed rather than the calls being run again,
+        // so the functions needn't be registered and each action is reduced once
+        let replayed = Instance::new().replay(&instance.history()).unwrap();
+        assert_eq!(replayed.state(), instance.state());
+        assert_eq!(replayed.history(), instance.history());
+        assert!(replayed.state().agent().has_entry(&entry.address()));
+    }
+
+    #[test]
+    fn dispatched_call_result_is_received_once_consumed() {
+        let mut instance = test_instance();
+        instance.register_function("test_zome", "greet", |params| {
+            Ok(format!("hello {}", params))
+        });
+
+        let call = ::nucleus::fncall::Call::new("test_zome", "greet").with_parameters("bob");
+        let mut receiver = instance.dispatch_call(call.clone()).unwrap();
+        assert_eq!(
+            *instance.pending_actions().back().unwrap(),
+            Nucleus(Call(call.clone()))
+        );
+        assert_eq!(receiver.try_recv(), Ok(None));
+
+        instance.consume_next_action().unwrap();
+        assert_eq!(receiver.try_recv(), Ok(Some(Ok("hello bob".to_string()))));
+        // the receiver took the result, so it isn't kept in the state
+        assert_eq!(instance.state().nucleus().call_result(&call), None);
+    }
+
+    #[test]
+    fn try_consuming_never_waits() {
+        let mut instance = test_instance();
+        assert_eq!(instance.try_consume_next_action(), Ok(None));
+
+        let entry = ::common::entry::Entry::new("test_type", "some content");
+        let id = instance.dispatch(Agent(Commit(entry.clone()))).unwrap();
+        assert_eq!(instance.try_consume_next_action(), Ok(Some(id)));
+        assert!(instance.state().agent().has_entry(&entry.address()));
+        assert_eq!(instance.try_consume_next_action(), Ok(None));
+
+        // a call in progress on another thread doesn't hold up trying
+        // the slow function waits at the barrier once when it starts and again before it
+        // finishes, so the other thread is known to be consuming in between
+        let barrier = ::std::sync::Arc::new(::std::sync::Barrier::new(2));
+        let slow_barrier = barrier.clone();
+        let mut processing = instance.clone();
+        processing.register_function("test_zome", "slow", move |_| {
+            slow_barrier.wait();
+            slow_barrier.wait();
+            Ok(String::new())
+        });
+        let call = ::nucleus::fncall::Call::new("test_zome", "slow");
+        processing.dispatch(Nucleus(Call(call))).unwrap();
+        processing.dispatch(Agent(Commit(entry))).unwrap();
+        let consuming = ::std::thread::spawn(move || processing.consume_next_action());
+        barrier.wait();
+        assert_eq!(
+            instance.try_consume_next_action(),
+            Err(::error::HolochainError::WouldBlock)
+        );
+        barrier.wait();
+        consuming.join().unwrap().unwrap();
+        assert_eq!(instance.pending_actions().len(), 1);
+    }
+
+    #[test]
+    fn cancelled_call_results_are_discarded() {
+        let mut instance = test_instance();
+        instance.register_function("test_zome", "greet", |params| {
+            Ok(format!("hello {}", params))
+        });
+
+        let call = ::nucleus::fncall::Call::new("test_zome", "greet").with_parameters("bob");
+        let mut receiver = instance.dispatch_call(call.clone()).unwrap();
+        instance.cancel_call(call.id());
+        assert!(instance.consume_next_action().unwrap().is_some());
+        assert!(receiver.try_recv().is_err());
+        assert_eq!(instance.state().nucleus().call_result(&call), None);
+        assert_eq!(instance.metrics().calls_total, 0);
+    }
+
+    #[test]
+    fn draining_consumes_all_pending_actions() {
+        let mut instance = test_instance();
+        instance.register_function("test_zome", "fail", |_| {
+            Err(::error::HolochainError::new("borked"))
+        });
+
+        let failing = ::nucleus::fncall::Call::new("test_zome", "fail");
+        let unknown = ::nucleus::fncall::Call::new("test_zome", "bogusfn");
+        instance.dispatch(Nucleus(Call(failing.clone()))).unwrap();
+        instance.dispatch(Nucleus(Call(unknown.clone()))).unwrap();
+        assert_eq!(instance.pending_actions().len(), 2);
+
+        instance.drain();
+        assert!(instance.pending_actions().is_empty());
+        assert!(instance.state().nucleus().call_result(&failing).is_some());
+        assert!(instance.state().nucleus().call_result(&unknown).is_some());
+    }
+
+    #[test]
+    fn processing_consumes_dispatched_actions() {
+        let mut instance = test_instance();
+        instance.register_function("test_zome", "greet", |params| {
+            Ok(format!("hello {}", params))
+        });
+        let receiver = instance.subscribe();
+        assert!(!instance.processing());
+
+        instance.start_processing().unwrap();
+        assert!(instance.processing());
+        assert!(instance.start_processing().is_err());
+
+        let call = ::nucleus::fncall::Call::new("test_zome", "greet").with_parameters("bob");
+        instance.dispatch(Nucleus(Call(call.clone()))).unwrap();
+        let state = receiver
+            .recv_timeout(::std::time::Duration::from_secs(5))
+            .expect("action was not consumed");
+        assert_eq!(
+            state.nucleus().call_result(&call),
+            Some(Ok("hello bob".to_string()))
+        );
+        assert!(instance.pending_actions().is_empty());
+
+        instance.stop_processing().unwrap();
+        assert!(!instance.processing());
+
+        // once stopped, dispatched actions wait to be consumed again
+        instance.dispatch(Nucleus(Call(call))).unwrap();
+        assert_eq!(instance.pending_actions().len(), 1);
+    }
+
+    #[test]
+    fn processing_runs_on_the_given_spawner() {
+        // counts the tasks it spawns, running them on threads
+        struct CountingSpawner(Mutex<usize>);
+        impl ::spawner::Spawner for CountingSpawner {
+            fn spawn(&self, task: Box<FnOnce() + Send>) {
+                *self.0.lock().unwrap() += 1;
+                ::spawner::ThreadSpawner.spawn(task);
+            }
+        }
+
+        let mut instance = test_instance();
+        instance.register_function("test_zome", "greet", |params| {
+            Ok(format!("hello {}", params))
+        });
+        let receiver = instance.subscribe();
+        let spawner = CountingSpawner(Mutex::new(0));
+        instance.start_processing_with(&spawner).unwrap();
+        assert_eq!(*spawner.0.lock().unwrap(), 1);
+
+        let call = ::nucleus::fncall::Call::new("test_zome", "greet").with_parameters("bob");
+        instance.dispatch(Nucleus(Call(call.clone()))).unwrap();
+        let state = receiver
+            .recv_timeout(::std::time::Duration::from_secs(5))
+            .expect("action was not consumed");
+        assert_eq!(
+            state.nucleus().call_result(&call),
+            Some(Ok("hello bob".to_string()))
+        );
+        instance.stop_processing().unwrap();
+        assert!(!instance.processing());
+    }
+
+    #[test]
+    fn middleware_runs_in_order_for_each_dispatched_action() {
+        let mut instance = test_instance();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        for name in &["first", "second"] {
+            let seen = seen.clone();
+            let name = name.to_string();
+            instance.add_middleware(move |action, _state| {
+                seen.lock().unwrap().push((name.clone(), action.clone()));
+            });
+        }
+
+        let entry = ::common::entry::Entry::new("test_type", "");
+        let call = ::nucleus::fncall::Call::new("test_zome", "bogusfn");
+        instance.dispatch(Agent(Commit(entry.clone()))).unwrap();
+        instance.dispatch(Nucleus(Call(call.clone()))).unwrap();
+        assert!(seen.lock().unwrap().is_empty());
+        instance.drain();
+
+        // the call's result isn't dispatched, so middleware doesn't see it
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![
+                ("first".to_string(), Agent(Commit(entry.clone()))),
+                ("second".to_string(), Agent(Commit(entry))),
+                ("first".to_string(), Nucleus(Call(call.clone()))),
+                ("second".to_string(), Nucleus(Call(call))),
+            ]
+        );
+    }
+
+    #[test]
+    fn middleware_sees_state_before_reduction() {
+        let mut instance = test_instance();
+        let entry = ::common::entry::Entry::new("test_type", "");
+        let address = entry.address();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        {
+            let seen = seen.clone();
+            instance.add_middleware(move |_action, state| {
+                seen.lock().unwrap().push(state.agent().has_entry(&address));
+            });
+        }
+
+        instance.dispatch(Agent(Commit(entry.clone()))).unwrap();
+        instance.consume_next_action().unwrap();
+        assert_eq!(*seen.lock().unwrap(), vec![false]);
+        assert!(instance.state().agent().has_entry(&entry.address()));
+    }
+
+    #[test]
+    fn fresh_instance_keeps_functions_and_observers() {
+        let mut instance = test_instance();
+        instance.register_function("test_zome", "greet", |_| Ok("hello".to_string()));
+        let receiver = instance.subscribe();
+
+        let mut fresh = instance.fresh();
+        assert_eq!(fresh.state(), ::state::State::new());
+        assert!(fresh.history().is_empty());
+
+        let mut dna = Dna::new();
+        let mut zome = ::hc_dna::zome::Zome::new();
+        zome.name = "test_zome".to_string();
+        dna.zomes.push(zome);
+        fresh.dispatch(Nucleus(InitApplication(dna))).unwrap();
+        fresh.consume_next_action().unwrap();
+        assert!(receiver.try_recv().is_ok());
+
+        let call = ::nucleus::fncall::Call::new("test_zome", "greet");
+        fresh.dispatch(Nucleus(Call(call.clone()))).unwrap();
+        fresh.consume_next_action().unwrap();
+        assert_eq!(
+            fresh.state().nucleus().call_result(&call),
+            Some(Ok("hello".to_string()))
+        );
+    }
+
+    // an instance whose context saves with persister, and a handle to the context's logger
+    fn persisting_instance(
+        persister: Arc<Mutex<SpyPersister>>,
+    ) -> (Instance, Arc<Mutex<::logger::SimpleLogger>>) {
+        let logger = Arc::new(Mutex::new(
+            ::logger::SimpleLogger::new().with_timestamps(false),
+        ));
+        let context = ::context::Context {
+            agent: ::hc_agent::Agent::from_string("bob"),
+            logger: logger.clone(),
+            persister,
+            entry_store: Arc::new(Mutex::new(::entry_store::SimpleEntryStore::new())),
+            dht: None,
+            instance_name: None,
+            genesis: None,
+        };
+        let mut instance = test_instance();
+        instance.set_context(Arc::new(context));
+        (instance, logger)
+    }
+
+    #[test]
+    fn state_is_saved_after_each_action() {
+        let spy = Arc::new(Mutex::new(SpyPersister::new()));
+        let (mut instance, _) = persisting_instance(spy.clone());
+
+        let entry = ::common::entry::Entry::new("test_type", "");
+        instance.dispatch(Agent(Commit(entry.clone()))).unwrap();
+        instance.dispatch(Agent(Commit(entry.clone()))).unwrap();
+        assert_eq!(spy.lock().unwrap().save_count(), 0);
+        instance.drain();
+        assert_eq!(spy.lock().unwrap().save_count(), 2);
+        assert_eq!(
+            spy.lock().unwrap().saved_states().last(),
+            Some(&instance.state())
+        );
+
+        // unless autosave is turned off
+        instance.set_autosave(false);
+        instance.dispatch(Agent(Commit(entry))).unwrap();
+        instance.consume_next_action().unwrap();
+        assert_eq!(spy.lock().unwrap().save_count(), 2);
+    }
+
+    #[test]
+    fn failing_to_save_is_logged() {
+        let spy = Arc::new(Mutex::new(SpyPersister::failing(
+            ::error::HolochainError::new("disk full"),
+        )));
+        let (mut instance, logger) = persisting_instance(spy.clone());
+
+        let entry = ::common::entry::Entry::new("test_type", "");
+        instance.dispatch(Agent(Commit(entry))).unwrap();
+        assert!(instance.consume_next_action().is_ok());
+        let lines = logger.lock().unwrap().dump();
+        assert_eq!(
+            lines,
+            vec!["[ERROR] could not save state: disk full".to_string()]
+        );
+    }
+
+    #[test]
+    fn observers_are_sent_state_after_each_action() {
+        let mut instance = test_instance();
+        let receiver = instance.subscribe();
+
+        let call = ::nucleus::fncall::Call::new("test_zome", "bogusfn");
+        instance.dispatch(Nucleus(Call(call.clone()))).unwrap();
+        assert!(receiver.try_recv().is_err());
+
+        instance.consume_next_action().unwrap();
+        let state = receiver.try_recv().unwrap();
+        assert_eq!(state, instance.state());
+        assert!(state.nucleus().call_result(&call).is_some());
+        assert!(receiver.try_recv().is_err());
+
+        // observers going away doesn't stop actions being consumed
+        drop(receiver);
+        instance.dispatch(Nucleus(Call(call))).unwrap();
+        assert!(instance.consume_next_action().is_ok());
+    }
+
+    #[test]
+    fn consuming_failing_call_reports_zome_and_function() {
+        let mut instance = test_instance();
+        instance.register_function("test_zome", "fail", |_| {
+            Err(::error::HolochainError::new("borked"))
+        });
+
+        let call = ::nucleus::fncall::Call::new("test_zome", "fail");
+        instance.dispatch(Nucleus(Call(call.clone()))).unwrap();
+        let expected = ::error::HolochainError::ZomeFunctionFailed {
+            zome: "test_zome".to_string(),
+            function: "fail".to_string(),
+            reason: "borked".to_string(),
+        };
+        assert_eq!(instance.consume_next_action(), Err(expected.clone()));
+        assert_eq!(
+            instance.state().nucleus().call_result(&call),
+            Some(Err(expected))
+        );
+    }
+
+    #[test]
+    fn commits_are_validated_by_entry_type() {
+        let mut instance = test_instance();
+        instance.register_validator("short", |entry| {
+            if entry.content().len() <= 5 {
+                Ok(())
+            } else {
+                Err("too long".to_string())
+            }
+        });
+
+        let valid = ::common::entry::Entry::new("short", "ok");
+        instance.dispatch(Agent(Commit(valid.clone()))).unwrap();
+        assert!(instance.consume_next_action().is_ok());
+        assert!(instance.state().agent().has_entry(&valid.address()));
+
+        let invalid = ::common::entry::Entry::new("short", "far too long");
+        instance.dispatch(Agent(Commit(invalid.clone()))).unwrap();
+        assert_eq!(
+            instance.consume_next_action(),
+            Err(::error::HolochainError::ValidationFailed(
+                "too long".to_string()
+            ))
+        );
+        assert!(!instance.state().agent().has_entry(&invalid.address()));
+
+        // types without a validator are accepted
+        let unvalidated = ::common::entry::Entry::new("long", "far too long");
+        instance
+            .dispatch(Agent(Commit(unvalidated.clone())))
+            .unwrap();
+        assert!(instance.consume_next_action().is_ok());
+        assert!(instance.state().agent().has_entry(&unvalidated.address()));
+    }
+
+    #[test]
+    fn commits_must_conform_to_entry_type_schema() {
+        let mut instance = Instance::new();
+        let mut dna = Dna::new();
+        let mut zome = ::hc_dna::zome::Zome::new();
+        zome.name = "test_zome".to_string();
+        for &(name, schema) in &[
+            ("post", Some(r#"{"type": "object", "required": ["title"]}"#)),
+            ("note", None),
+        ] {
+            let mut entry_type = ::hc_dna::zome::entry_types::EntryType::new();
+            entry_type.name = name.to_string();
+            entry_type.schema = schema.map(|schema| schema.to_string());
+            zome.entry_types.push(entry_type);
+        }
+        dna.zomes.push(zome);
+        instance
+            .dispatch_and_consume(Nucleus(InitApplication(dna)))
+            .unwrap();
+
+        let conforming = ::common::entry::Entry::new("post", r#"{"title": "hello"}"#);
+        assert!(instance
+            .dispatch_and_consume(Agent(Commit(conforming.clone())))
+            .is_ok());
+        assert!(instance.state().agent().has_entry(&conforming.address()));
+
+        let nonconforming = ::common::entry::Entry::new("post", r#"{"body": "hello"}"#);
+        assert_eq!(
+            instance.dispatch_and_consume(Agent(Commit(nonconforming.clone()))),
+            Err(::error::HolochainError::ValidationFailed(
+                "/: missing required property title".to_string()
+            ))
+        );
+        assert!(!instance.state().agent().has_entry(&nonconforming.address()));
+
+        // types without a schema take any content, even if it isn't json
+        let unchecked = ::common::entry::Entry::new("note", "just text");
+        assert!(instance
+            .dispatch_and_consume(Agent(Commit(unchecked.clone())))
+            .is_ok());
+        assert!(instance.state().agent().has_entry(&unchecked.address()));
+    }
+
+    #[test]
+    fn consuming_call_to_unknown_zome() {
+        let mut instance = test_instance();
+        instance.register_function("test_zome", "greet", |_| Ok("hello".to_string()));
+
+        let call = ::nucleus::fncall::Call::new("bogozome", "greet");
+        instance.dispatch(Nucleus(Call(call.clone()))).unwrap();
+        instance.consume_next_action().unwrap();
+        assert_eq!(
+            instance.state().nucleus().call_result(&call),
+            Some(Err(::error::HolochainError::ZomeNotFound(
+                "bogozome".to_string()
+            )))
+        );
+    }
+
+    #[test]
+    fn consuming_actions_records_history() {
+        let mut instance = test_instance();
+        assert_eq!(instance.history().len(), 1);
+
+        let call = ::nucleus::fncall::Call::new("test_zome", "bogusfn");
+        instance.dispatch(Nucleus(Call(call.clone()))).unwrap();
+        // dispatched actions are only recorded once they are consumed
+        assert_eq!(instance.history().len(), 1);
+        instance.consume_next_action().unwrap();
+
+        let history = instance.history();
+        assert_eq!(history.len(), 3);
+        match history[0] {
+            Nucleus(InitApplication(_)) => assert!(true),
+            _ => assert!(false),
+        };
+        assert_eq!(history[1], Nucleus(Call(call.clone())));
+        assert_eq!(
+            history[2],
+            Nucleus(ReturnResult(
+                call,
+                Err(::error::HolochainError::NotImplemented)
+            ))
+        );
+    }
 }
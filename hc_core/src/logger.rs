@@ -8,28 +8,134 @@ gets emitted globaly from the container.
 
 use std::fmt;
 
+/// the severity of a logged message, from least to most severe
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            LogLevel::Debug => "DEBUG",
+            LogLevel::Info => "INFO",
+            LogLevel::Warn => "WARN",
+            LogLevel::Error => "ERROR",
+        };
+        write!(f, "{}", name)
+    }
+}
+
 /// trait that defines the logging functionality that hc_core requires
-pub trait Logger: fmt::Debug {
-    fn log(&mut self, msg: String);
+/// (loggers must be Send, as instances process actions on their own thread)
+pub trait Logger: fmt::Debug + Send {
+    fn log(&mut self, level: LogLevel, msg: String);
+
+    /// logs a message from the named instance, prefixing the message with the name
+    /// unless the logger keeps it separately
+    fn log_from(&mut self, instance_name: Option<&str>, level: LogLevel, msg: String) {
+        let msg = match instance_name {
+            Some(name) => format!("[{}] {}", name, msg),
+            None => msg,
+        };
+        self.log(level, msg);
+    }
+
+    /// returns the lines that have been logged, for loggers that keep them
+    fn dump(&self) -> Vec<String> {
+        Vec::new()
+    }
 }
 
+/// how many lines a SimpleLogger or JsonLogger keeps unless it is created with a capacity
+pub const DEFAULT_CAPACITY: usize = 1024;
+
+/// a logger that prints messages prefixed with an RFC3339 timestamp and keeps the most
+/// recent ones, so that a long running container doesn't keep every line it ever logged
 #[derive(Clone)]
 pub struct SimpleLogger {
-    //    log: Vec<String>,
+    log: VecDeque<String>,
+    capacity: usize,
+    level: LogLevel,
+    timestamps: bool,
 }
 
 extern crate chrono;
 
-use self::chrono::Local;
+use self::chrono::{DateTime, Utc};
+use serde_json;
+use std::collections::VecDeque;
+use std::time::SystemTime;
 
 impl Logger for SimpleLogger {
-    fn log(&mut self, msg: String) {
-        let date = Local::now();
-        println!("{}:{}", date.format("%Y-%m-%d %H:%M:%S"), msg);
+    fn log(&mut self, level: LogLevel, msg: String) {
+        if level < self.level {
+            return;
+        }
+        let line = if self.timestamps {
+            let date: DateTime<Utc> = SystemTime::now().into();
+            format!("[{}] {} {}", level, date.to_rfc3339(), msg)
+        } else {
+            format!("[{}] {}", level, msg)
+        };
+        println!("{}", line);
+        keep_line(&mut self.log, self.capacity, line);
+    }
+
+    fn dump(&self) -> Vec<String> {
+        self.log.iter().cloned().collect()
+    }
+}
+
+// keeps line as the most recent of at most capacity lines, dropping the oldest to make room
+fn keep_line(log: &mut VecDeque<String>, capacity: usize, line: String) {
+    if capacity == 0 {
+        return;
+    }
+    if log.len() == capacity {
+        log.pop_front();
+    }
+    log.push_back(line);
+}
+
+impl SimpleLogger {
+    /// create a logger that keeps the DEFAULT_CAPACITY most recent messages of every level,
+    /// timestamped
+    pub fn new() -> Self {
+        SimpleLogger {
+            log: VecDeque::new(),
+            capacity: DEFAULT_CAPACITY,
+            level: LogLevel::Debug,
+            timestamps: true,
+        }
+    }
+
+    /// drop messages less severe than level
+    pub fn with_level(mut self, level: LogLevel) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// keep only the capacity most recent messages
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// prefix each message with an RFC3339 timestamp if timestamps is true
+    pub fn with_timestamps(mut self, timestamps: bool) -> Self {
+        self.timestamps = timestamps;
+        self
+    }
+}
+
+impl Default for SimpleLogger {
+    fn default() -> Self {
+        Self::new()
     }
-    /*    fn new() -> SimpleLogger {
-        SimpleLogger {}
-    }*/
 }
 
 impl fmt::Debug for SimpleLogger {
@@ -37,3 +143,244 @@ impl fmt::Debug for SimpleLogger {
         write!(f, "<empty>")
     }
 }
+
+/// a logger that emits each message as a line of json with `level`, `timestamp`,
+/// `instance` and `message` fields, for log pipelines that ingest json,
+/// keeping the most recent lines as SimpleLogger does
+#[derive(Clone, Debug)]
+pub struct JsonLogger {
+    log: VecDeque<String>,
+    capacity: usize,
+    level: LogLevel,
+}
+
+#[derive(Serialize)]
+struct JsonLine<'a> {
+    level: String,
+    timestamp: String,
+    instance: Option<&'a str>,
+    message: &'a str,
+}
+
+impl Logger for JsonLogger {
+    fn log(&mut self, level: LogLevel, msg: String) {
+        self.log_from(None, level, msg);
+    }
+
+    fn log_from(&mut self, instance_name: Option<&str>, level: LogLevel, msg: String) {
+        if level < self.level {
+            return;
+        }
+        let date: DateTime<Utc> = SystemTime::now().into();
+        let line = JsonLine {
+            level: level.to_string(),
+            timestamp: date.to_rfc3339(),
+            instance: instance_name,
+            message: &msg,
+        };
+        // a struct of strings always serializes
+        let line = serde_json::to_string(&line).unwrap();
+        println!("{}", line);
+        keep_line(&mut self.log, self.capacity, line);
+    }
+
+    fn dump(&self) -> Vec<String> {
+        self.log.iter().cloned().collect()
+    }
+}
+
+impl JsonLogger {
+    /// create a logger that keeps the DEFAULT_CAPACITY most recent messages of every level
+    pub fn new() -> Self {
+        JsonLogger {
+            log: VecDeque::new(),
+            capacity: DEFAULT_CAPACITY,
+            level: LogLevel::Debug,
+        }
+    }
+
+    /// drop messages less severe than level
+    pub fn with_level(mut self, level: LogLevel) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// keep only the capacity most recent messages
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+}
+
+impl Default for JsonLogger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct NullLogger {}
+
+    impl Logger for NullLogger {
+        fn log(&mut self, _level: LogLevel, _msg: String) {}
+    }
+
+    #[test]
+    fn dump_defaults_to_empty() {
+        let mut logger = NullLogger {};
+        logger.log(LogLevel::Info, "some message".to_string());
+        assert!(logger.dump().is_empty());
+    }
+
+    #[test]
+    fn can_dump_simple_logger() {
+        let mut logger = SimpleLogger::new();
+        assert!(logger.dump().is_empty());
+
+        logger.log(LogLevel::Info, "first".to_string());
+        logger.log(LogLevel::Info, "second".to_string());
+        let lines = logger.dump();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with(" first"));
+        assert!(lines[1].ends_with(" second"));
+    }
+
+    #[test]
+    fn simple_logger_keeps_most_recent_lines() {
+        let mut logger = SimpleLogger::new().with_capacity(2).with_timestamps(false);
+        for msg in &["first", "second", "third"] {
+            logger.log(LogLevel::Info, msg.to_string());
+        }
+        assert_eq!(
+            logger.dump(),
+            vec!["[INFO] second".to_string(), "[INFO] third".to_string()]
+        );
+
+        let mut logger = SimpleLogger::new().with_capacity(0);
+        logger.log(LogLevel::Info, "dropped".to_string());
+        assert!(logger.dump().is_empty());
+    }
+
+    #[test]
+    fn simple_logger_prefixes_level() {
+        let mut logger = SimpleLogger::new();
+        logger.log(LogLevel::Debug, "a".to_string());
+        logger.log(LogLevel::Info, "b".to_string());
+        logger.log(LogLevel::Warn, "c".to_string());
+        logger.log(LogLevel::Error, "d".to_string());
+        let lines = logger.dump();
+        assert!(lines[0].starts_with("[DEBUG] "));
+        assert!(lines[1].starts_with("[INFO] "));
+        assert!(lines[2].starts_with("[WARN] "));
+        assert!(lines[3].starts_with("[ERROR] "));
+    }
+
+    #[test]
+    fn simple_logger_drops_messages_below_level() {
+        let mut logger = SimpleLogger::new().with_level(LogLevel::Warn);
+        logger.log(LogLevel::Debug, "dropped".to_string());
+        logger.log(LogLevel::Info, "dropped".to_string());
+        logger.log(LogLevel::Warn, "kept".to_string());
+        logger.log(LogLevel::Error, "kept".to_string());
+        let lines = logger.dump();
+        assert_eq!(lines.len(), 2);
+        assert!(lines.iter().all(|line| line.ends_with(" kept")));
+    }
+
+    #[test]
+    fn simple_logger_prefixes_timestamps_by_default() {
+        let mut logger = SimpleLogger::new();
+        logger.log(LogLevel::Info, "first".to_string());
+        logger.log(LogLevel::Error, "second".to_string());
+        let lines = logger.dump();
+        assert_eq!(lines.len(), 2);
+        for (line, prefix) in lines.iter().zip(&["[INFO] ", "[ERROR] "]) {
+            assert!(line.starts_with(prefix));
+            let timestamp = line[prefix.len()..].split(' ').next().unwrap();
+            assert!(DateTime::parse_from_rfc3339(timestamp).is_ok());
+        }
+        assert!(lines[0].ends_with(" first"));
+        assert!(lines[1].ends_with(" second"));
+
+        let mut logger = SimpleLogger::new().with_timestamps(false);
+        logger.log(LogLevel::Info, "untimed".to_string());
+        assert_eq!(logger.dump(), vec!["[INFO] untimed".to_string()]);
+
+        // settings combine
+        let mut logger = SimpleLogger::new()
+            .with_level(LogLevel::Warn)
+            .with_capacity(1)
+            .with_timestamps(false);
+        logger.log(LogLevel::Info, "dropped".to_string());
+        logger.log(LogLevel::Warn, "pushed out".to_string());
+        logger.log(LogLevel::Error, "kept".to_string());
+        assert_eq!(logger.dump(), vec!["[ERROR] kept".to_string()]);
+    }
+
+    #[test]
+    fn simple_logger_prefixes_instance_name() {
+        let mut logger = SimpleLogger::new().with_timestamps(false);
+        logger.log_from(Some("MyApp"), LogLevel::Info, "named".to_string());
+        logger.log_from(None, LogLevel::Info, "unnamed".to_string());
+        assert_eq!(
+            logger.dump(),
+            vec![
+                "[INFO] [MyApp] named".to_string(),
+                "[INFO] unnamed".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn json_logger_emits_json_objects() {
+        let mut logger = JsonLogger::new();
+        logger.log_from(Some("MyApp"), LogLevel::Warn, "some message".to_string());
+        logger.log(LogLevel::Info, "unnamed".to_string());
+        let lines = logger.dump();
+        assert_eq!(lines.len(), 2);
+
+        let line: serde_json::Value = serde_json::from_str(&lines[0]).unwrap();
+        assert_eq!(line["level"], "WARN");
+        assert_eq!(line["instance"], "MyApp");
+        assert_eq!(line["message"], "some message");
+        let timestamp = line["timestamp"].as_str().unwrap();
+        assert!(DateTime::parse_from_rfc3339(timestamp).is_ok());
+
+        let line: serde_json::Value = serde_json::from_str(&lines[1]).unwrap();
+        assert_eq!(line["level"], "INFO");
+        assert!(line["instance"].is_null());
+        assert_eq!(line["message"], "unnamed");
+    }
+
+    #[test]
+    fn json_logger_drops_messages_below_level() {
+        let mut logger = JsonLogger::new().with_level(LogLevel::Error);
+        logger.log(LogLevel::Warn, "dropped".to_string());
+        logger.log(LogLevel::Error, "kept".to_string());
+        assert_eq!(logger.dump().len(), 1);
+    }
+
+    #[test]
+    fn json_logger_keeps_most_recent_lines() {
+        let mut logger = JsonLogger::new().with_capacity(2);
+        for msg in &["first", "second", "third"] {
+            logger.log(LogLevel::Info, msg.to_string());
+        }
+        let lines: Vec<serde_json::Value> = logger
+            .dump()
+            .iter()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["message"], "second");
+        assert_eq!(lines[1]["message"], "third");
+
+        let mut logger = JsonLogger::new().with_capacity(0);
+        logger.log(LogLevel::Info, "dropped".to_string());
+        assert!(logger.dump().is_empty());
+    }
+}
@@ -0,0 +1,49 @@
+/*!
+metrics holds the counters an instance keeps about the work it has done,
+for operators to monitor it with.
+*/
+
+use std::time::Duration;
+
+/// how many zome function calls an instance has consumed, and how long they took
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Metrics {
+    pub calls_total: u64,
+    /// calls whose result was an error, including calls to missing functions
+    pub calls_failed: u64,
+    /// how long running the most recently consumed call took, if there has been one
+    pub last_call_duration: Option<Duration>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// count a consumed call that took duration to run
+    pub fn record_call(&mut self, duration: Duration, failed: bool) {
+        self.calls_total += 1;
+        if failed {
+            self.calls_failed += 1;
+        }
+        self.last_call_duration = Some(duration);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_record_calls() {
+        let mut metrics = Metrics::new();
+        assert_eq!(metrics.calls_total, 0);
+        assert_eq!(metrics.last_call_duration, None);
+
+        metrics.record_call(Duration::from_millis(5), false);
+        metrics.record_call(Duration::from_millis(2), true);
+        assert_eq!(metrics.calls_total, 2);
+        assert_eq!(metrics.calls_failed, 1);
+        assert_eq!(metrics.last_call_duration, Some(Duration::from_millis(2)));
+    }
+}
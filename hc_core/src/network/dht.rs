@@ -0,0 +1,66 @@
+/*!
+dht is where committed entries are published for other agents to fetch, and fetched from
+when they aren't held locally; transports implement DhtStore to put them on a network.
+*/
+
+use common::entry::Entry;
+use error::HolochainError;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// trait that defines how entries are shared with other agents
+/// (stores must be Send, as instances process actions on their own thread)
+pub trait DhtStore: Send {
+    /// make entry available to other agents at address
+    fn publish(&mut self, address: &str, entry: &Entry) -> Result<(), HolochainError>;
+    /// the entry published at address, if it can be found
+    fn fetch(&self, address: &str) -> Option<Entry>;
+}
+
+/// keeps published entries in memory; clones share the same entries, so giving each
+/// of several instances a clone simulates them being nodes on the same network
+#[derive(Default, Clone, Debug)]
+pub struct MemoryDht {
+    entries: Arc<Mutex<HashMap<String, Entry>>>,
+}
+
+impl DhtStore for MemoryDht {
+    fn publish(&mut self, address: &str, entry: &Entry) -> Result<(), HolochainError> {
+        self.entries
+            .lock()
+            .map_err(|_| HolochainError::new("could not lock the dht"))?
+            .insert(address.to_string(), entry.clone());
+        Ok(())
+    }
+
+    fn fetch(&self, address: &str) -> Option<Entry> {
+        self.entries
+            .lock()
+            .ok()
+            .and_then(|entries| entries.get(address).cloned())
+    }
+}
+
+impl MemoryDht {
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_fetch_across_nodes() {
+        let mut alice = MemoryDht::new();
+        let bob = alice.clone();
+        let entry = Entry::new("post", "hello");
+        assert_eq!(bob.fetch(&entry.address()), None);
+
+        alice.publish(&entry.address(), &entry).unwrap();
+        assert_eq!(bob.fetch(&entry.address()), Some(entry.clone()));
+        assert_eq!(alice.fetch(&entry.address()), Some(entry));
+        assert_eq!(MemoryDht::new().fetch("bogus address"), None);
+    }
+}
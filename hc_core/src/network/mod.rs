@@ -1,3 +1,5 @@
+pub mod dht;
+
 use common::entry::Hash;
 
 #[derive(Clone, Debug, PartialEq)]
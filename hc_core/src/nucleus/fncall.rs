@@ -1,19 +1,72 @@
-/// Represents a function call, with the function name and its parameters
+use std::sync::atomic::{AtomicUsize, Ordering};
 
-#[derive(Clone, Debug, PartialEq)]
-pub struct Params {}
+/// source of unique ids so that results can be matched back up with their calls
+static NEXT_CALL_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Represents a function call, with the zome and function name and its parameters
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Call {
-    name: String,
-    params: Params,
+    id: usize,
+    zome_name: String,
+    fn_name: String,
+    parameters: String,
 }
 
 impl Call {
-    pub fn new(name: &str) -> Self {
+    /// create a new call to the named function in the named zome, passing no parameters
+    pub fn new(zome_name: &str, fn_name: &str) -> Self {
         Call {
-            name: name.to_string(),
-            params: Params {},
+            id: NEXT_CALL_ID.fetch_add(1, Ordering::SeqCst),
+            zome_name: zome_name.to_string(),
+            fn_name: fn_name.to_string(),
+            parameters: String::new(),
         }
     }
+
+    /// pass parameters to the function as a json string
+    pub fn with_parameters(mut self, parameters: &str) -> Self {
+        self.parameters = parameters.to_string();
+        self
+    }
+
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    pub fn zome_name(&self) -> String {
+        self.zome_name.clone()
+    }
+
+    pub fn fn_name(&self) -> String {
+        self.fn_name.clone()
+    }
+
+    pub fn parameters(&self) -> String {
+        self.parameters.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_instantiate() {
+        let call = Call::new("some_zome", "some_fn").with_parameters(r#"{"a":1}"#);
+        assert_eq!(call.zome_name(), "some_zome");
+        assert_eq!(call.fn_name(), "some_fn");
+        assert_eq!(call.parameters(), r#"{"a":1}"#);
+
+        let call = Call::new("some_zome", "some_fn");
+        assert_eq!(call.parameters(), "");
+    }
+
+    #[test]
+    fn calls_have_unique_ids() {
+        let call1 = Call::new("some_zome", "some_fn");
+        let call2 = Call::new("some_zome", "some_fn");
+        assert_ne!(call1.id(), call2.id());
+        assert_ne!(call1, call2);
+    }
 }
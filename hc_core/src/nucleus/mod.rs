@@ -3,15 +3,41 @@ use hc_dna::Dna;
 
 pub mod fncall;
 pub mod ribosome;
+pub mod signal;
 
 //use self::ribosome::*;
+use error::HolochainError;
 use state;
-use std::rc::Rc;
+use std::collections::HashMap;
+use std::sync::Arc;
 
-#[derive(Clone, Debug, PartialEq, Default)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
 pub struct NucleusState {
-    dna: Option<Dna>,
+    // shared between states, so that reducing doesn't copy the whole dna
+    dna: Option<Arc<Dna>>,
     initialized: bool,
+    call_results: HashMap<usize, Result<String, HolochainError>>,
+    // the capability grants made so far, keyed by the token they were granted with;
+    // states saved before grants were kept in state have none
+    #[serde(default)]
+    grants: HashMap<String, CapabilityGrant>,
+}
+
+/// access to a set of functions, given to whoever holds its token
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CapabilityGrant {
+    pub functions: Vec<String>,
+}
+
+impl CapabilityGrant {
+    pub fn new(functions: Vec<String>) -> Self {
+        CapabilityGrant { functions }
+    }
+
+    /// whether the grant gives access to the function
+    pub fn allows(&self, fn_name: &str) -> bool {
+        self.functions.iter().any(|function| function == fn_name)
+    }
 }
 
 impl NucleusState {
@@ -19,39 +45,143 @@ impl NucleusState {
         NucleusState {
             dna: None,
             initialized: false,
+            call_results: HashMap::new(),
+            grants: HashMap::new(),
         }
     }
 
     pub fn dna(&self) -> Option<Dna> {
-        self.dna.clone()
+        self.dna.as_ref().map(|dna| (**dna).clone())
     }
 
     pub fn initialized(&self) -> bool {
         self.initialized
     }
+
+    /// checks whether the current dna contains a zome with the given name
+    pub fn has_zome(&self, zome_name: &str) -> bool {
+        match self.dna {
+            Some(ref dna) => dna.get_zome(zome_name).is_some(),
+            None => false,
+        }
+    }
+
+    /// checks whether the given zome of the current dna declares a function with the given name
+    pub fn has_zome_function(&self, zome_name: &str, fn_name: &str) -> bool {
+        match self.dna.as_ref().and_then(|dna| dna.get_zome(zome_name)) {
+            Some(zome) => zome.has_function(fn_name),
+            None => false,
+        }
+    }
+
+    /// the wasm code implementing a function of the given zome of the current dna, if it has any
+    pub fn zome_function_code(&self, zome_name: &str, fn_name: &str) -> Option<Vec<u8>> {
+        self.dna
+            .as_ref()
+            .and_then(|dna| dna.get_zome(zome_name))
+            .and_then(|zome| zome.code_for(fn_name))
+            .map(|code| code.code.clone())
+    }
+
+    /// an application property of the current dna
+    pub fn dna_property(&self, key: &str) -> Option<String> {
+        self.dna.as_ref().and_then(|dna| dna.get_property(key))
+    }
+
+    /// the JSON Schema that entries of entry_type must conform to, if the current dna
+    /// gives one; the first zome declaring entry_type with a schema decides
+    pub fn entry_schema(&self, entry_type: &str) -> Option<String> {
+        self.dna.as_ref().and_then(|dna| {
+            dna.zomes
+                .iter()
+                .flat_map(|zome| zome.entry_types.iter())
+                .filter(|declared| declared.name == entry_type)
+                .filter_map(|declared| declared.schema.clone())
+                .next()
+        })
+    }
+
+    /// the capability grant made with token, if there is one
+    pub fn capability_grant(&self, token: &str) -> Option<CapabilityGrant> {
+        self.grants.get(token).cloned()
+    }
+
+    /// the names of the fields that differ between this state and other
+    pub fn changed_fields(&self, other: &NucleusState) -> Vec<String> {
+        let mut fields = Vec::new();
+        if self.dna != other.dna {
+            fields.push("dna".to_string());
+        }
+        if self.initialized != other.initialized {
+            fields.push("initialized".to_string());
+        }
+        if self.call_results != other.call_results {
+            fields.push("call_results".to_string());
+        }
+        if self.grants != other.grants {
+            fields.push("grants".to_string());
+        }
+        fields
+    }
+
+    /// the result of a function call, if it has been returned yet
+    pub fn call_result(&self, call: &fncall::Call) -> Option<Result<String, HolochainError>> {
+        self.call_results.get(&call.id()).cloned()
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum Action {
     InitApplication(Dna),
     Call(fncall::Call),
+    ReturnResult(fncall::Call, Result<String, HolochainError>),
+    /// the result of a call has been taken by whoever was waiting for it,
+    /// so it no longer needs keeping in state
+    TakeResult(fncall::Call),
+    /// record a grant under the token that gives access to it
+    GrantCapability(String, CapabilityGrant),
+    /// a signal emitted by a function while it was called
+    EmitSignal(signal::Signal),
 }
 
-pub fn reduce(old_state: Rc<NucleusState>, action: &state::Action) -> Rc<NucleusState> {
-    match *action {
-        state::Action::Nucleus(ref nucleus_action) => {
-            let mut new_state: NucleusState = (*old_state).clone();
-            match *nucleus_action {
-                Action::InitApplication(ref dna) => {
-                    if !new_state.initialized {
-                        new_state.dna = Some(dna.clone());
-                        new_state.initialized = true;
-                    }
-                }
-                Action::Call(_) => {}
-            }
-            Rc::new(new_state)
+pub fn reduce(old_state: Arc<NucleusState>, action: &state::Action) -> Arc<NucleusState> {
+    let nucleus_action = match *action {
+        state::Action::Nucleus(ref nucleus_action) => nucleus_action,
+        _ => return old_state,
+    };
+    match *nucleus_action {
+        Action::InitApplication(ref dna) if !old_state.initialized => {
+            let mut new_state = (*old_state).clone();
+            new_state.dna = Some(Arc::new(dna.clone()));
+            new_state.initialized = true;
+            Arc::new(new_state)
+        }
+        // a call to a zome the dna doesn't have can't run, so it gets its result straight away
+        Action::Call(ref call) if !old_state.has_zome(&call.zome_name()) => {
+            let mut new_state = (*old_state).clone();
+            new_state.call_results.insert(
+                call.id(),
+                Err(HolochainError::ZomeNotFound(call.zome_name())),
+            );
+            Arc::new(new_state)
+        }
+        Action::ReturnResult(ref call, ref result) => {
+            let mut new_state = (*old_state).clone();
+            new_state.call_results.insert(call.id(), result.clone());
+            Arc::new(new_state)
+        }
+        Action::TakeResult(ref call) if old_state.call_results.contains_key(&call.id()) => {
+            let mut new_state = (*old_state).clone();
+            new_state.call_results.remove(&call.id());
+            Arc::new(new_state)
+        }
+        Action::GrantCapability(ref token, ref grant) => {
+            let mut new_state = (*old_state).clone();
+            new_state.grants.insert(token.clone(), grant.clone());
+            Arc::new(new_state)
         }
+        // signals are recorded in the history and sent to the container,
+        // so like calls and results nobody has, they leave the state as it was
         _ => old_state,
     }
 }
@@ -69,11 +199,86 @@ mod tests {
         assert_eq!(state.initialized, false);
     }
 
+    #[test]
+    fn can_check_for_zomes() {
+        let state = NucleusState::new();
+        assert!(!state.has_zome("test_zome"));
+
+        let mut dna = Dna::new();
+        let mut zome = hc_dna::zome::Zome::new();
+        zome.name = "test_zome".to_string();
+        dna.zomes.push(zome);
+        let state = reduce(Arc::new(state), &Nucleus(InitApplication(dna)));
+        assert!(state.has_zome("test_zome"));
+        assert!(!state.has_zome("other_zome"));
+    }
+
+    #[test]
+    fn can_check_for_zome_functions() {
+        let state = NucleusState::new();
+        assert!(!state.has_zome_function("test_zome", "test_fn"));
+
+        let mut dna = Dna::new();
+        let mut zome = hc_dna::zome::Zome::new();
+        zome.name = "test_zome".to_string();
+        let mut capability = hc_dna::zome::capabilities::Capability::new();
+        let mut fn_declaration = hc_dna::zome::capabilities::FnDeclaration::new();
+        fn_declaration.name = "test_fn".to_string();
+        capability.fn_declarations.push(fn_declaration);
+        zome.capabilities.push(capability);
+        dna.zomes.push(zome);
+        let state = reduce(Arc::new(state), &Nucleus(InitApplication(dna)));
+        assert!(state.has_zome_function("test_zome", "test_fn"));
+        assert!(!state.has_zome_function("test_zome", "other_fn"));
+        assert!(!state.has_zome_function("other_zome", "test_fn"));
+    }
+
+    #[test]
+    fn can_read_dna_properties() {
+        let state = NucleusState::new();
+        assert_eq!(state.dna_property("network"), None);
+
+        let mut dna = Dna::new();
+        dna.set_property("network", "testnet");
+        let state = reduce(Arc::new(state), &Nucleus(InitApplication(dna)));
+        assert_eq!(state.dna_property("network"), Some("testnet".to_string()));
+        assert_eq!(state.dna_property("bogus"), None);
+    }
+
+    #[test]
+    fn can_grant_capabilities() {
+        let grant = CapabilityGrant::new(vec!["admin_fn".to_string()]);
+        let state = Arc::new(NucleusState::new());
+        assert_eq!(state.capability_grant("token"), None);
+
+        let state = reduce(
+            state,
+            &Nucleus(GrantCapability("token".to_string(), grant.clone())),
+        );
+        assert_eq!(state.capability_grant("token"), Some(grant.clone()));
+        assert_eq!(state.capability_grant("other token"), None);
+
+        assert!(grant.allows("admin_fn"));
+        assert!(!grant.allows("other_fn"));
+    }
+
+    #[test]
+    fn can_list_changed_fields() {
+        let state = Arc::new(NucleusState::new());
+        assert!(state.changed_fields(&state).is_empty());
+
+        let reduced_state = reduce(state.clone(), &Nucleus(InitApplication(Dna::new())));
+        assert_eq!(
+            state.changed_fields(&reduced_state),
+            vec!["dna".to_string(), "initialized".to_string()]
+        );
+    }
+
     #[test]
     fn can_reduce_initialize_action() {
         let dna = Dna::new();
         let action = Nucleus(InitApplication(dna));
-        let state = Rc::new(NucleusState::new()); // initialize to bogus value
+        let state = Arc::new(NucleusState::new()); // initialize to bogus value
         let reduced_state = reduce(state.clone(), &action);
         assert!(reduced_state.initialized, true);
 
@@ -84,10 +289,63 @@ mod tests {
 
     #[test]
     fn can_reduce_call_action() {
-        let call = fncall::Call::new("bogusfn");
-        let action = Nucleus(Call(call));
-        let state = Rc::new(NucleusState::new()); // initialize to bogus value
-        let reduced_state = reduce(state.clone(), &action);
+        let mut dna = Dna::new();
+        let mut zome = hc_dna::zome::Zome::new();
+        zome.name = "test_zome".to_string();
+        dna.zomes.push(zome);
+        let state = reduce(
+            Arc::new(NucleusState::new()),
+            &Nucleus(InitApplication(dna)),
+        );
+
+        let call = fncall::Call::new("test_zome", "bogusfn");
+        let reduced_state = reduce(state.clone(), &Nucleus(Call(call.clone())));
         assert_eq!(state, reduced_state);
+        assert_eq!(reduced_state.call_result(&call), None);
+    }
+
+    #[test]
+    fn reducing_call_to_unknown_zome_records_zome_not_found() {
+        let call = fncall::Call::new("bogozome", "bogusfn");
+        let action = Nucleus(Call(call.clone()));
+        let state = Arc::new(NucleusState::new()); // initialize to bogus value
+        let reduced_state = reduce(state.clone(), &action);
+        assert_eq!(
+            reduced_state.call_result(&call),
+            Some(Err(HolochainError::ZomeNotFound("bogozome".to_string())))
+        );
+    }
+
+    #[test]
+    fn can_reduce_return_result_action() {
+        let call = fncall::Call::new("bogozome", "bogusfn");
+        let state = Arc::new(NucleusState::new()); // initialize to bogus value
+        assert_eq!(state.call_result(&call), None);
+
+        let action = Nucleus(ReturnResult(call.clone(), Ok("some result".to_string())));
+        let reduced_state = reduce(state.clone(), &action);
+        assert_eq!(
+            reduced_state.call_result(&call),
+            Some(Ok("some result".to_string()))
+        );
+
+        // other calls have no result
+        let other_call = fncall::Call::new("bogozome", "bogusfn");
+        assert_eq!(reduced_state.call_result(&other_call), None);
+    }
+
+    #[test]
+    fn can_reduce_take_result_action() {
+        let call = fncall::Call::new("bogozome", "bogusfn");
+        let action = Nucleus(ReturnResult(call.clone(), Ok("some result".to_string())));
+        let state = reduce(Arc::new(NucleusState::new()), &action);
+
+        let taken_state = reduce(state.clone(), &Nucleus(TakeResult(call.clone())));
+        assert_eq!(taken_state.call_result(&call), None);
+        assert_eq!(*taken_state, NucleusState::new());
+
+        // taking a result that isn't there leaves the state as it was
+        let retaken_state = reduce(taken_state.clone(), &Nucleus(TakeResult(call)));
+        assert!(Arc::ptr_eq(&retaken_state, &taken_state));
     }
 }
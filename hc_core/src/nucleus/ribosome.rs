@@ -1 +1,83 @@
-trait Ribosome {}
+use common::entry::Entry;
+use error::HolochainError;
+use std::sync::Arc;
+use wasmi::{ImportsBuilder, Module, ModuleInstance, NopExternals, RuntimeValue};
+
+/// A zome function implemented natively in rust, which takes the call's json parameters
+/// and returns its result. Takes the place of the zome's wasm code, e.g. in tests.
+pub type NativeFunction = Arc<Fn(&str) -> Result<String, HolochainError> + Send + Sync>;
+
+/// An entry validation function implemented natively in rust, which returns the reason
+/// an entry is invalid. Stands in for the dna's validation code in the same way.
+pub type NativeValidator = Arc<Fn(&Entry) -> Result<(), String> + Send + Sync>;
+
+/// Run the function fn_name exported by a zome's wasm code with the call's json parameters.
+/// The parameters are written at the start of the module's exported "memory", the function
+/// is passed their length, and it returns where its result is as an i64 holding the result's
+/// offset in the upper 32 bits and its length in the lower 32 bits.
+pub fn call(wasm: &[u8], fn_name: &str, params: &str) -> Result<String, HolochainError> {
+    let module = Module::from_buffer(wasm).map_err(wasm_error)?;
+    let instance = ModuleInstance::new(&module, &ImportsBuilder::default())
+        .map_err(wasm_error)?
+        .run_start(&mut NopExternals)
+        .map_err(wasm_error)?;
+    let memory = match instance.export_by_name("memory") {
+        Some(export) => match export.as_memory() {
+            Some(memory) => memory.clone(),
+            None => return Err(HolochainError::new("wasm export \"memory\" isn't a memory")),
+        },
+        None => return Err(HolochainError::new("wasm module exports no memory")),
+    };
+    memory.set(0, params.as_bytes()).map_err(wasm_error)?;
+    let args = [RuntimeValue::I32(params.len() as i32)];
+    let location = match instance
+        .invoke_export(fn_name, &args, &mut NopExternals)
+        .map_err(wasm_error)?
+    {
+        Some(RuntimeValue::I64(location)) => location as u64,
+        _ => {
+            return Err(HolochainError::new(&format!(
+                "wasm function {} didn't return the location of its result",
+                fn_name
+            )))
+        }
+    };
+    let result = memory
+        .get((location >> 32) as u32, (location & 0xffff_ffff) as usize)
+        .map_err(wasm_error)?;
+    String::from_utf8(result).map_err(|err| HolochainError::new(&err.to_string()))
+}
+
+fn wasm_error<E: ::std::fmt::Display>(err: E) -> HolochainError {
+    HolochainError::ErrorGeneric(format!("wasm failed: {}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn echo_wasm() -> Vec<u8> {
+        include_bytes!("../../test-fixtures/echo.wasm").to_vec()
+    }
+
+    #[test]
+    fn can_call_wasm_function() {
+        assert_eq!(
+            call(&echo_wasm(), "echo", r#"{"a":1}"#),
+            Ok(r#"{"a":1}"#.to_string())
+        );
+        assert_eq!(call(&echo_wasm(), "echo", ""), Ok("".to_string()));
+    }
+
+    #[test]
+    fn calling_bad_wasm_fails() {
+        match call(&echo_wasm(), "bogusfn", "") {
+            Err(HolochainError::ErrorGeneric(_)) => assert!(true),
+            _ => assert!(false),
+        };
+        match call(&[0, 1, 2, 3], "echo", "") {
+            Err(HolochainError::ErrorGeneric(_)) => assert!(true),
+            _ => assert!(false),
+        };
+    }
+}
@@ -0,0 +1,55 @@
+use std::cell::RefCell;
+
+/// a notification pushed by a zome function to the container, e.g. for it to forward to a UI
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Signal {
+    pub name: String,
+    /// json describing what happened
+    pub payload: String,
+}
+
+impl Signal {
+    pub fn new(name: &str, payload: &str) -> Self {
+        Signal {
+            name: name.to_string(),
+            payload: payload.to_string(),
+        }
+    }
+}
+
+// functions run on the thread consuming their call, so signals are collected per thread
+thread_local! {
+    static EMITTED: RefCell<Vec<Signal>> = RefCell::default();
+}
+
+/// emit a signal from the zome function that is running; it is sent to the container
+/// once the function's result has been stored
+pub fn emit_signal(name: &str, payload: &str) {
+    EMITTED.with(|emitted| emitted.borrow_mut().push(Signal::new(name, payload)));
+}
+
+/// the signals emitted on this thread since this was last called, oldest first,
+/// for the instance to collect those of the function it ran
+pub fn take_emitted() -> Vec<Signal> {
+    EMITTED.with(|emitted| emitted.borrow_mut().drain(..).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_take_emitted_signals() {
+        assert!(take_emitted().is_empty());
+        emit_signal("posted", r#"{"title":"hello"}"#);
+        emit_signal("liked", "{}");
+        assert_eq!(
+            take_emitted(),
+            vec![
+                Signal::new("posted", r#"{"title":"hello"}"#),
+                Signal::new("liked", "{}"),
+            ]
+        );
+        assert!(take_emitted().is_empty());
+    }
+}
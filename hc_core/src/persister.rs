@@ -1,20 +1,27 @@
 use error::HolochainError;
+use serde_json;
 use state::State;
+use std::fs::{self, File};
+use std::io::{ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
 
 /// trait that defines the persistence functionality that hc_core requires
-pub trait Persister {
-    fn save(&mut self, state: &State);
+/// (persisters must be Send, as instances process actions on their own thread)
+pub trait Persister: Send {
+    fn save(&mut self, state: &State) -> Result<(), HolochainError>;
     fn load(&self) -> Result<Option<State>, HolochainError>;
 }
 
+/// keeps the last saved state in memory, so it is lost when the process exits
 #[derive(Default, Clone, PartialEq)]
 pub struct SimplePersister {
     state: Option<State>,
 }
 
 impl Persister for SimplePersister {
-    fn save(&mut self, state: &State) {
+    fn save(&mut self, state: &State) -> Result<(), HolochainError> {
         self.state = Some(state.clone());
+        Ok(())
     }
     fn load(&self) -> Result<Option<State>, HolochainError> {
         Ok(self.state.clone())
@@ -27,9 +34,54 @@ impl SimplePersister {
     }
 }
 
+/// persists the state as json in a file, so it survives the process exiting
+#[derive(Clone, PartialEq)]
+pub struct FilePersister {
+    path: PathBuf,
+}
+
+impl Persister for FilePersister {
+    // writes to a temporary file which is then moved over the store,
+    // so a crash mid-save leaves the previously saved state intact
+    fn save(&mut self, state: &State) -> Result<(), HolochainError> {
+        let json = serde_json::to_string(state)?;
+        let tmp_path = self.tmp_path();
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(json.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    // a missing file means nothing has been saved yet
+    fn load(&self) -> Result<Option<State>, HolochainError> {
+        let mut json = String::new();
+        match File::open(&self.path).and_then(|mut file| file.read_to_string(&mut json)) {
+            Err(ref e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            result => result?,
+        };
+        Ok(Some(serde_json::from_str(&json)?))
+    }
+}
+
+impl FilePersister {
+    pub fn new(path: &Path) -> Self {
+        FilePersister {
+            path: path.to_path_buf(),
+        }
+    }
+
+    fn tmp_path(&self) -> PathBuf {
+        let mut file_name = self.path.file_name().unwrap_or_default().to_os_string();
+        file_name.push(".tmp");
+        self.path.with_file_name(file_name)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::env;
 
     #[test]
     fn can_instantiate() {
@@ -49,12 +101,99 @@ mod tests {
 
         let mut state = State::new();
 
-        let entry = ::common::entry::Entry::new(&"some hash".to_string());
+        let entry = ::common::entry::Entry::new("test_type", "some hash");
         let action = ::state::Action::Agent(::agent::Action::Commit(entry));
         let new_state = state.reduce(&action);
 
-        store.save(&new_state);
+        store.save(&new_state).unwrap();
 
         assert_eq!(store.load().unwrap().unwrap(), new_state);
     }
+
+    #[test]
+    fn can_roundtrip_latest_state() {
+        let mut store = SimplePersister::new();
+        let mut state = State::new();
+        state = state.reduce(&::state::Action::Nucleus(
+            ::nucleus::Action::InitApplication(::hc_dna::Dna::new()),
+        ));
+        store.save(&state).unwrap();
+
+        let base = ::common::entry::Entry::new("person", "alice");
+        let target = ::common::entry::Entry::new("person", "bob");
+        for action in &[
+            ::agent::Action::Commit(base.clone()),
+            ::agent::Action::Commit(target.clone()),
+            ::agent::Action::LinkEntries {
+                base: base.address(),
+                target: target.address(),
+                tag: "follows".to_string(),
+            },
+        ] {
+            state = state.reduce(&::state::Action::Agent(action.clone()));
+        }
+        store.save(&state).unwrap();
+
+        // only the last saved state is kept
+        let loaded = store.load().unwrap().unwrap();
+        assert_eq!(loaded, state);
+        assert!(loaded.nucleus().initialized());
+        assert_eq!(
+            loaded.agent().addresses(),
+            vec![base.address(), target.address()]
+        );
+        assert_eq!(
+            loaded.agent().links(&base.address(), "follows"),
+            vec![target.address()]
+        );
+    }
+
+    // a path in the temp dir that is unique to this test run
+    fn test_path(name: &str) -> PathBuf {
+        let file_name = format!("hc_core_{}_{}.json", name, ::std::process::id());
+        let path = env::temp_dir().join(file_name);
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn file_persister_loads_nothing_from_missing_file() {
+        let path = test_path("missing");
+        let store = FilePersister::new(&path);
+        assert_eq!(store.load(), Ok(None));
+    }
+
+    #[test]
+    fn file_persister_can_roundtrip() {
+        let path = test_path("roundtrip");
+
+        let mut state = State::new();
+        let dna = ::hc_dna::Dna::new();
+        let action = ::state::Action::Nucleus(::nucleus::Action::InitApplication(dna));
+        let new_state = state.reduce(&action);
+
+        let mut store = FilePersister::new(&path);
+        store.save(&new_state).unwrap();
+        assert!(!store.tmp_path().exists());
+
+        // a new persister on the same path sees the saved state
+        let store = FilePersister::new(&path);
+        assert_eq!(store.load().unwrap().unwrap(), new_state);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn file_persister_fails_on_corrupt_file() {
+        let path = test_path("corrupt");
+        fs::write(&path, "not json").unwrap();
+
+        let store = FilePersister::new(&path);
+        match store.load() {
+            Err(HolochainError::SerializationError(_)) => {}
+            other => panic!("expected a serialization error, got {:?}", other),
+        }
+
+        fs::remove_file(&path).unwrap();
+    }
 }
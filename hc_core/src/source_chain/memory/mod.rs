@@ -1,15 +1,13 @@
 use std;
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct SourceChain {
     pairs: Vec<super::Pair>,
 }
 
 impl SourceChain {
     pub fn new() -> SourceChain {
-        SourceChain {
-            pairs: Vec::new(),
-        }
+        SourceChain { pairs: Vec::new() }
     }
 }
 
@@ -57,7 +55,7 @@ mod tests {
     // @TODO - do we want to expose something like this as a general utility?
     // @see https://github.com/holochain/holochain-rust/issues/34
     fn test_pair(previous_pair: Option<&Pair>, s: &str) -> Pair {
-        let e = Entry::new(&s.to_string());
+        let e = Entry::new("test_type", s);
         let previous = match previous_pair {
             Some(p) => Some(p.header.hash()),
             None => None,
@@ -79,7 +77,10 @@ mod tests {
         chain.push(&p3);
 
         // iter() should iterate over references
-        assert_eq!(vec![&p3, &p2, &p1], chain.iter().rev().collect::<Vec<&Pair>>());
+        assert_eq!(
+            vec![&p3, &p2, &p1],
+            chain.iter().rev().collect::<Vec<&Pair>>()
+        );
 
         // iter() should support functional logic
         assert_eq!(
@@ -115,7 +116,8 @@ mod tests {
         assert_eq!(
             vec![&p1],
             (&chain)
-                .into_iter().rev()
+                .into_iter()
+                .rev()
                 .filter(|p| p.header.previous() == None)
                 .collect::<Vec<&Pair>>()
         );
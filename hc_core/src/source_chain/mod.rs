@@ -4,7 +4,7 @@ use std;
 use common::entry::Entry;
 use common::entry::Header;
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct Pair {
     header: Header,
     entry: Entry,
@@ -40,7 +40,7 @@ mod tests {
 
     #[test]
     fn new_pair() {
-        let e1 = Entry::new(&String::from("some content"));
+        let e1 = Entry::new("test_type", "some content");
         let h1 = Header::new(None, &e1);
         assert_eq!(h1.entry(), e1.hash());
         assert_eq!(h1.previous(), None);
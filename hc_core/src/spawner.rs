@@ -0,0 +1,42 @@
+/*!
+spawner abstracts over how an instance runs its background work, so that embedders can
+run it on the runtime they already use instead of on a thread of its own.
+*/
+
+use std::thread;
+
+/// runs tasks in the background, e.g. on a thread, a thread pool or an async runtime
+pub trait Spawner {
+    /// run task to completion in the background; the task blocks while it waits for
+    /// work, so async runtimes should run it where blocking is allowed
+    fn spawn(&self, task: Box<FnOnce() + Send>);
+}
+
+/// the default spawner, which runs each task on a new thread
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ThreadSpawner;
+
+impl Spawner for ThreadSpawner {
+    fn spawn(&self, task: Box<FnOnce() + Send>) {
+        thread::spawn(task);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::channel;
+    use std::time::Duration;
+
+    #[test]
+    fn thread_spawner_runs_tasks() {
+        let (sender, receiver) = channel();
+        ThreadSpawner.spawn(Box::new(move || {
+            sender.send(thread::current().id()).unwrap()
+        }));
+        let id = receiver
+            .recv_timeout(Duration::from_secs(5))
+            .expect("task didn't run");
+        assert_ne!(id, thread::current().id());
+    }
+}
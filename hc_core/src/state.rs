@@ -1,6 +1,9 @@
 use agent::AgentState;
+use error::HolochainError;
 use nucleus::NucleusState;
-use std::rc::Rc;
+use serde_json::{self, Value};
+use std::collections::BTreeSet;
+use std::sync::Arc;
 
 #[derive(Clone, Debug, PartialEq)]
 #[allow(unknown_lints)]
@@ -11,33 +14,93 @@ pub enum Action {
     Nucleus(::nucleus::Action),
 }
 
-#[derive(Clone, PartialEq, Debug, Default)]
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, Default)]
 pub struct State {
-    nucleus: Rc<NucleusState>,
-    agent: Rc<AgentState>,
+    nucleus: Arc<NucleusState>,
+    agent: Arc<AgentState>,
 }
 
 impl State {
     pub fn new() -> Self {
         State {
-            nucleus: Rc::new(NucleusState::new()),
-            agent: Rc::new(AgentState::new()),
+            nucleus: Arc::new(NucleusState::new()),
+            agent: Arc::new(AgentState::new()),
         }
     }
 
     pub fn reduce(&mut self, action: &Action) -> Self {
         State {
-            nucleus: ::nucleus::reduce(Rc::clone(&self.nucleus), action),
-            agent: ::agent::reduce(Rc::clone(&self.agent), action),
+            nucleus: ::nucleus::reduce(Arc::clone(&self.nucleus), action),
+            agent: ::agent::reduce(Arc::clone(&self.agent), action),
         }
     }
 
-    pub fn nucleus(&self) -> Rc<NucleusState> {
-        Rc::clone(&self.nucleus)
+    pub fn nucleus(&self) -> Arc<NucleusState> {
+        Arc::clone(&self.nucleus)
     }
 
-    pub fn agent(&self) -> Rc<AgentState> {
-        Rc::clone(&self.agent)
+    pub fn agent(&self) -> Arc<AgentState> {
+        Arc::clone(&self.agent)
+    }
+
+    /// the whole state as pretty printed json, for debugging and tooling; keys are sorted
+    /// so that equal states always give the same json, which can be diffed
+    pub fn to_json(&self) -> Result<String, HolochainError> {
+        Ok(serde_json::to_string_pretty(&sort_keys(
+            serde_json::to_value(self)?,
+        ))?)
+    }
+
+    /// what changed going from this state to other
+    pub fn diff(&self, other: &State) -> StateDiff {
+        // only the entries that haven't been removed count, sorted so the differences are too
+        let entries: BTreeSet<String> = self.agent.addresses().into_iter().collect();
+        let other_entries: BTreeSet<String> = other.agent.addresses().into_iter().collect();
+        let added_entries = other_entries.difference(&entries).cloned().collect();
+        let removed_entries = entries.difference(&other_entries).cloned().collect();
+        StateDiff {
+            added_entries,
+            removed_entries,
+            nucleus_changes: self.nucleus.changed_fields(&other.nucleus),
+        }
+    }
+}
+
+/// the changes between two states, as returned by `State::diff`
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct StateDiff {
+    /// the addresses of the entries only in the later state, in order
+    pub added_entries: Vec<String>,
+    /// the addresses of the entries only in the earlier state, in order
+    pub removed_entries: Vec<String>,
+    /// the names of the nucleus fields that differ
+    pub nucleus_changes: Vec<String>,
+}
+
+impl StateDiff {
+    /// whether the two states were the same
+    pub fn is_empty(&self) -> bool {
+        self.added_entries.is_empty()
+            && self.removed_entries.is_empty()
+            && self.nucleus_changes.is_empty()
+    }
+}
+
+// rebuilds value with the keys of its objects sorted, as maps serialize in any order
+fn sort_keys(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut fields: Vec<(String, Value)> = map.into_iter().collect();
+            fields.sort_by(|a, b| a.0.cmp(&b.0));
+            Value::Object(
+                fields
+                    .into_iter()
+                    .map(|(key, value)| (key, sort_keys(value)))
+                    .collect(),
+            )
+        }
+        Value::Array(values) => Value::Array(values.into_iter().map(sort_keys).collect()),
+        value => value,
     }
 }
 
@@ -45,7 +108,7 @@ impl State {
 TODO: write macro for DRY reducer functions
 macro_rules! reducer {
     ($func_name:ident) => (
-        fn reducer(old_state: Rc<$state_type>, action: &_Action) -> Rc<$state_type>  {
+        fn reducer(old_state: Arc<$state_type>, action: &_Action) -> Arc<$state_type>  {
             // The `stringify!` macro converts an `ident` into a string.
             println!("You called {:?}()",
                      stringify!($func_name));
@@ -53,3 +116,37 @@ macro_rules! reducer {
     )
 }
 */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use agent::Action::*;
+    use common::entry::Entry;
+    use state::Action::*;
+
+    #[test]
+    fn can_dump_state_as_json() {
+        let entry = Entry::new("test_type", "some content");
+        let state = State::new().reduce(&Agent(Commit(entry.clone())));
+        let json = state.to_json().unwrap();
+        assert!(json.contains(&entry.address()));
+        assert!(json.contains("some content"));
+        assert!(json.contains("\"call_results\""));
+    }
+
+    #[test]
+    fn json_dump_is_stable() {
+        let commit_all = || {
+            let mut state = State::new();
+            for content in &["a", "b", "c", "d", "e", "f"] {
+                state = state.reduce(&Agent(Commit(Entry::new("test_type", content))));
+            }
+            state
+        };
+        // each state's maps iterate in their own order
+        assert_eq!(
+            commit_all().to_json().unwrap(),
+            commit_all().to_json().unwrap()
+        );
+    }
+}
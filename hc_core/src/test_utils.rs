@@ -0,0 +1,160 @@
+/*!
+test_utils holds helpers for testing code that runs Holochain instances,
+available to other crates' tests through the "test_utils" feature.
+*/
+
+use context::Context;
+use entry_store::SimpleEntryStore;
+use error::HolochainError;
+use hc_agent::Agent;
+use hc_dna::zome::capabilities::Membrane;
+use hc_dna::zome::ZomeBuilder;
+use hc_dna::Dna;
+use logger::{LogLevel, Logger};
+use persister::{Persister, SimplePersister};
+use state::State;
+use std::sync::{Arc, Mutex};
+
+/// a persister that records every state it is asked to save, so tests can check
+/// what was persisted without touching disk
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SpyPersister {
+    saved_states: Vec<State>,
+    failure: Option<HolochainError>,
+}
+
+impl Persister for SpyPersister {
+    fn save(&mut self, state: &State) -> Result<(), HolochainError> {
+        self.saved_states.push(state.clone());
+        match self.failure {
+            Some(ref err) => Err(err.clone()),
+            None => Ok(()),
+        }
+    }
+
+    fn load(&self) -> Result<Option<State>, HolochainError> {
+        Ok(self.saved_states.last().cloned())
+    }
+}
+
+impl SpyPersister {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// create a persister that records saves but then fails them with err
+    pub fn failing(err: HolochainError) -> Self {
+        SpyPersister {
+            saved_states: Vec::new(),
+            failure: Some(err),
+        }
+    }
+
+    /// every state saved so far, oldest first
+    pub fn saved_states(&self) -> Vec<State> {
+        self.saved_states.clone()
+    }
+
+    /// how many times save has been called
+    pub fn save_count(&self) -> usize {
+        self.saved_states.len()
+    }
+}
+
+/// a logger that keeps every message as it was logged, without levels or timestamps,
+/// so tests can check exactly what was logged
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MockLogger {
+    log: Vec<String>,
+}
+
+impl Logger for MockLogger {
+    fn log(&mut self, _level: LogLevel, msg: String) {
+        self.log.push(msg);
+    }
+
+    fn dump(&self) -> Vec<String> {
+        self.log.clone()
+    }
+}
+
+/// a context for agent that persists in memory, along with the `MockLogger` it logs to
+pub fn mock_context(agent: Agent) -> (Arc<Context>, Arc<Mutex<Logger>>) {
+    let logger: Arc<Mutex<Logger>> = Arc::new(Mutex::new(MockLogger::default()));
+    let context = Context {
+        agent,
+        logger: logger.clone(),
+        persister: Arc::new(Mutex::new(SimplePersister::new())),
+        entry_store: Arc::new(Mutex::new(SimpleEntryStore::new())),
+        dht: None,
+        instance_name: None,
+        genesis: None,
+    };
+    (Arc::new(context), logger)
+}
+
+/// a valid dna called name, with a single zome called "test_zome" whose public
+/// capability "main" declares "test_fn"; its uuid is fixed, so its hash is too
+pub fn mock_dna(name: &str) -> Dna {
+    let mut dna = Dna::new();
+    dna.name = name.to_string();
+    dna.uuid = "00000000-0000-0000-0000-000000000000".to_string();
+    let zome = ZomeBuilder::new()
+        .with_capability("main", Membrane::Public)
+        .with_function("main", "test_fn")
+        .build();
+    // a fresh dna has no zomes to clash with
+    dna.add_zome("test_zome", zome).unwrap();
+    dna
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_saved_states() {
+        let mut spy = SpyPersister::new();
+        assert_eq!(spy.save_count(), 0);
+        assert_eq!(spy.load(), Ok(None));
+
+        let state = State::new();
+        spy.save(&state).unwrap();
+        spy.save(&state).unwrap();
+        assert_eq!(spy.save_count(), 2);
+        assert_eq!(spy.saved_states(), vec![state.clone(), state.clone()]);
+        assert_eq!(spy.load(), Ok(Some(state)));
+    }
+
+    #[test]
+    fn mock_context_logs_to_returned_logger() {
+        let (context, logger) = mock_context(Agent::from_string("bob"));
+        assert_eq!(context.agent, Agent::from_string("bob"));
+        context.log("hello").unwrap();
+        context.named("test").log("again").unwrap();
+        assert_eq!(
+            logger.lock().unwrap().dump(),
+            vec!["hello".to_string(), "[test] again".to_string()]
+        );
+    }
+
+    #[test]
+    fn mock_dna_is_valid_and_deterministic() {
+        let dna = mock_dna("TestApp");
+        assert_eq!(dna.validate(), Ok(()));
+        assert_eq!(dna.name, "TestApp");
+        assert!(dna.get_zome("test_zome").unwrap().is_public("test_fn"));
+        assert_eq!(dna.hash(), mock_dna("TestApp").hash());
+        assert_ne!(dna.hash(), mock_dna("OtherApp").hash());
+    }
+
+    #[test]
+    fn failing_spy_still_records_saves() {
+        let mut spy = SpyPersister::failing(HolochainError::new("disk full"));
+        assert_eq!(
+            spy.save(&State::new()),
+            Err(HolochainError::new("disk full"))
+        );
+        assert_eq!(spy.save_count(), 1);
+    }
+}
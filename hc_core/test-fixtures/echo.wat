@@ -0,0 +1,7 @@
+;; a zome whose "echo" function returns its params unchanged:
+;; they are written at offset 0 of memory, so the result is at offset 0 with the same length
+(module
+  (memory (export "memory") 1)
+  (func (export "echo") (param $params_len i32) (result i64)
+    local.get $params_len
+    i64.extend_i32_u))
@@ -0,0 +1,182 @@
+/*!
+container holds the Holochain instances that a container application is running,
+keyed by an instance id chosen by the container.
+*/
+
+use super::Holochain;
+use hc_core::error::HolochainError;
+use std::collections::HashMap;
+
+/// a registry of named Holochain instances
+#[derive(Clone, Default)]
+pub struct Container {
+    instances: HashMap<String, Holochain>,
+}
+
+impl Container {
+    pub fn new() -> Self {
+        Container {
+            instances: HashMap::new(),
+        }
+    }
+
+    /// add an instance under id, failing if that id is already taken
+    pub fn add_instance(&mut self, id: &str, hc: Holochain) -> Result<(), HolochainError> {
+        if self.instances.contains_key(id) {
+            return Err(HolochainError::ErrorGeneric(format!(
+                "instance already exists: {}",
+                id
+            )));
+        }
+        self.instances.insert(id.to_string(), hc);
+        Ok(())
+    }
+
+    /// remove the instance with id, handing it back to the caller
+    pub fn remove_instance(&mut self, id: &str) -> Result<Holochain, HolochainError> {
+        self.instances.remove(id).ok_or_else(|| not_found(id))
+    }
+
+    pub fn get(&self, id: &str) -> Result<&Holochain, HolochainError> {
+        self.instances.get(id).ok_or_else(|| not_found(id))
+    }
+
+    pub fn get_mut(&mut self, id: &str) -> Result<&mut Holochain, HolochainError> {
+        self.instances.get_mut(id).ok_or_else(|| not_found(id))
+    }
+
+    /// the ids of all the instances, sorted
+    pub fn instance_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self.instances.keys().cloned().collect();
+        ids.sort();
+        ids
+    }
+
+    /// the number of instances
+    pub fn len(&self) -> usize {
+        self.instances.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.instances.is_empty()
+    }
+
+    /// start every instance that isn't already active
+    pub fn start_all(&mut self) -> Result<(), HolochainError> {
+        for hc in self.instances.values_mut() {
+            if !hc.active() {
+                hc.start()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// stop every instance that is active
+    pub fn stop_all(&mut self) -> Result<(), HolochainError> {
+        for hc in self.instances.values_mut() {
+            if hc.active() {
+                hc.stop()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn not_found(id: &str) -> HolochainError {
+    HolochainError::ErrorGeneric(format!("instance not found: {}", id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hc_agent::Agent;
+    use hc_core::test_utils::{mock_context, mock_dna};
+
+    fn test_hc(name: &str) -> Holochain {
+        let (context, _) = mock_context(Agent::from_string("bob"));
+        Holochain::new(mock_dna(name), context).unwrap()
+    }
+
+    #[test]
+    fn can_add_get_and_remove() {
+        let mut container = Container::new();
+        container.add_instance("app1", test_hc("App1")).unwrap();
+        container.add_instance("app2", test_hc("App2")).unwrap();
+
+        assert_eq!(
+            container.instance_ids(),
+            vec!["app1".to_string(), "app2".to_string()]
+        );
+        assert_eq!(
+            container.get("app1").unwrap().name(),
+            Some("App1".to_string())
+        );
+
+        let removed = container.remove_instance("app1").unwrap();
+        assert_eq!(removed.name(), Some("App1".to_string()));
+        assert!(container.get("app1").is_err());
+        assert_eq!(
+            container.get("app2").unwrap().name(),
+            Some("App2".to_string())
+        );
+    }
+
+    #[test]
+    fn can_list_instances() {
+        let mut container = Container::new();
+        assert!(container.is_empty());
+        assert!(container.instance_ids().is_empty());
+
+        for id in &["charlie", "alpha", "bravo"] {
+            container.add_instance(id, test_hc(id)).unwrap();
+        }
+        assert_eq!(container.len(), 3);
+        assert!(!container.is_empty());
+        assert_eq!(container.instance_ids(), vec!["alpha", "bravo", "charlie"]);
+
+        container.remove_instance("alpha").unwrap();
+        assert_eq!(container.len(), 2);
+        assert_eq!(container.instance_ids(), vec!["bravo", "charlie"]);
+    }
+
+    #[test]
+    fn adding_duplicate_id_fails() {
+        let mut container = Container::new();
+        container.add_instance("app", test_hc("App1")).unwrap();
+        assert_eq!(
+            container.add_instance("app", test_hc("App2")),
+            Err(HolochainError::new("instance already exists: app"))
+        );
+        assert_eq!(
+            container.get("app").unwrap().name(),
+            Some("App1".to_string())
+        );
+    }
+
+    #[test]
+    fn unknown_id_fails() {
+        let mut container = Container::new();
+        let expected = HolochainError::new("instance not found: bogus");
+        assert_eq!(container.get("bogus").err(), Some(expected.clone()));
+        assert_eq!(container.get_mut("bogus").err(), Some(expected.clone()));
+        assert_eq!(container.remove_instance("bogus").err(), Some(expected));
+    }
+
+    #[test]
+    fn can_start_and_stop_all() {
+        let mut container = Container::new();
+        container.add_instance("app1", test_hc("App1")).unwrap();
+        container.add_instance("app2", test_hc("App2")).unwrap();
+
+        // instances that are already active are left alone
+        container.get_mut("app1").unwrap().start().unwrap();
+        container.start_all().unwrap();
+        assert!(container.get("app1").unwrap().active());
+        assert!(container.get("app2").unwrap().active());
+
+        container.get_mut("app2").unwrap().stop().unwrap();
+        container.stop_all().unwrap();
+        assert!(!container.get("app1").unwrap().active());
+        assert!(!container.get("app2").unwrap().active());
+    }
+}
@@ -0,0 +1,255 @@
+/*!
+json_rpc exposes the instances of a container to external clients (UIs, CLIs)
+as JSON-RPC 2.0 methods named "instance/zome/function".
+*/
+
+use container::Container;
+use hc_core::error::HolochainError;
+use serde_json::{self, Value};
+
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INTERNAL_ERROR: i64 = -32603;
+/// the instance being called isn't running
+pub const INSTANCE_NOT_ACTIVE: i64 = -32000;
+/// the zome function was called but failed
+pub const FUNCTION_FAILED: i64 = -32001;
+/// the zome function is declared but has no implementation
+pub const NOT_IMPLEMENTED: i64 = -32002;
+/// the instance couldn't take any more actions
+pub const QUEUE_FULL: i64 = -32003;
+/// the request's "cap_token" doesn't grant access to the zome function
+pub const CAPABILITY_DENIED: i64 = -32004;
+
+/// the JSON-RPC error code a failed call is reported with
+pub fn error_code(err: &HolochainError) -> i64 {
+    match err {
+        HolochainError::ZomeNotFound(_) | HolochainError::ZomeFunctionNotFound { .. } => {
+            METHOD_NOT_FOUND
+        }
+        HolochainError::InstanceNotActive => INSTANCE_NOT_ACTIVE,
+        HolochainError::ZomeFunctionFailed { .. } => FUNCTION_FAILED,
+        HolochainError::NotImplemented => NOT_IMPLEMENTED,
+        HolochainError::QueueFull => QUEUE_FULL,
+        HolochainError::CapabilityDenied { .. } => CAPABILITY_DENIED,
+        _ => INTERNAL_ERROR,
+    }
+}
+
+/// dispatches JSON-RPC requests to zome function calls on the container's instances
+pub struct JsonRpcHandler {
+    container: Container,
+}
+
+impl JsonRpcHandler {
+    pub fn new(container: Container) -> Self {
+        JsonRpcHandler { container }
+    }
+
+    pub fn container(&self) -> &Container {
+        &self.container
+    }
+
+    pub fn container_mut(&mut self) -> &mut Container {
+        &mut self.container
+    }
+
+    /// handle a JSON-RPC request, returning the JSON of its response or error object
+    pub fn handle_request(&mut self, request: &str) -> String {
+        let request: Value = match serde_json::from_str(request) {
+            Ok(request) => request,
+            Err(err) => return error_response(Value::Null, PARSE_ERROR, &err.to_string()),
+        };
+        let id = request.get("id").cloned().unwrap_or(Value::Null);
+        let method = match request.get("method").and_then(Value::as_str) {
+            Some(method) => method.to_string(),
+            None => return error_response(id, INVALID_REQUEST, "method must be a string"),
+        };
+        let parts: Vec<&str> = method.split('/').collect();
+        if parts.len() != 3 {
+            return error_response(
+                id,
+                METHOD_NOT_FOUND,
+                &format!("method must be instance/zome/function: {}", method),
+            );
+        }
+        // zome functions take their parameters as a string, so anything else is passed as json
+        let params = match request.get("params") {
+            None | Some(Value::Null) => String::new(),
+            Some(Value::String(params)) => params.clone(),
+            Some(params) => params.to_string(),
+        };
+        let hc = match self.container.get_mut(parts[0]) {
+            Ok(hc) => hc,
+            Err(err) => return error_response(id, METHOD_NOT_FOUND, &err.to_string()),
+        };
+        let cap_token = request.get("cap_token").and_then(Value::as_str);
+        match hc.call(parts[1], parts[2], &params, cap_token) {
+            Ok(result) => json!({"jsonrpc": "2.0", "result": result, "id": id}).to_string(),
+            Err(err) => error_response(id, error_code(&err), &err.to_string()),
+        }
+    }
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> String {
+    json!({
+        "jsonrpc": "2.0",
+        "error": {"code": code, "message": message},
+        "id": id,
+    })
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hc_agent::Agent;
+    use hc_core::test_utils::mock_context;
+    use hc_dna::zome::capabilities::Membrane;
+    use hc_dna::zome::ZomeBuilder;
+    use hc_dna::Dna;
+    use Holochain;
+
+    fn test_handler() -> JsonRpcHandler {
+        let mut dna = Dna::new();
+        dna.name = "TestApp".to_string();
+        let zome = ZomeBuilder::new()
+            .with_capability("public", Membrane::Public)
+            .with_function("public", "echo_fn")
+            .with_function("public", "fail_fn")
+            .with_capability("admin", Membrane::Agent)
+            .with_function("admin", "admin_fn")
+            .build();
+        dna.add_zome("test_zome", zome).unwrap();
+        let (context, _) = mock_context(Agent::from_string("bob"));
+        let mut hc = Holochain::new(dna, context).unwrap();
+        hc.register_function("test_zome", "echo_fn", |params| Ok(params.to_string()));
+        hc.register_function("test_zome", "admin_fn", |_| Ok("admin result".to_string()));
+        hc.register_function("test_zome", "fail_fn", |_| {
+            Err(HolochainError::new("borked"))
+        });
+        hc.start().unwrap();
+
+        let mut container = Container::new();
+        container.add_instance("app", hc).unwrap();
+        JsonRpcHandler::new(container)
+    }
+
+    fn handle(handler: &mut JsonRpcHandler, request: &str) -> Value {
+        serde_json::from_str(&handler.handle_request(request)).unwrap()
+    }
+
+    fn expect_error(response: Value, code: i64) {
+        assert_eq!(response["jsonrpc"], json!("2.0"));
+        assert_eq!(response["error"]["code"], json!(code));
+        assert!(response.get("result").is_none());
+    }
+
+    #[test]
+    fn can_call_zome_function() {
+        let mut handler = test_handler();
+        let response = handle(
+            &mut handler,
+            r#"{"jsonrpc": "2.0", "method": "app/test_zome/echo_fn", "params": "hello", "id": 1}"#,
+        );
+        assert_eq!(
+            response,
+            json!({"jsonrpc": "2.0", "result": "hello", "id": 1})
+        );
+
+        // structured params reach the function as json
+        let response = handle(
+            &mut handler,
+            r#"{"jsonrpc": "2.0", "method": "app/test_zome/echo_fn", "params": {"a": 1}, "id": 2}"#,
+        );
+        assert_eq!(
+            response,
+            json!({"jsonrpc": "2.0", "result": r#"{"a":1}"#, "id": 2})
+        );
+    }
+
+    #[test]
+    fn can_present_capability_token() {
+        let mut handler = test_handler();
+        let token = handler
+            .container_mut()
+            .get_mut("app")
+            .unwrap()
+            .grant_capability(vec!["admin_fn".to_string()])
+            .unwrap();
+        let request = json!({
+            "jsonrpc": "2.0",
+            "method": "app/test_zome/admin_fn",
+            "cap_token": token,
+            "id": 1,
+        });
+        let response = handle(&mut handler, &request.to_string());
+        assert_eq!(
+            response,
+            json!({"jsonrpc": "2.0", "result": "admin result", "id": 1})
+        );
+
+        expect_error(
+            handle(
+                &mut handler,
+                r#"{"jsonrpc": "2.0", "method": "app/test_zome/admin_fn", "id": 1}"#,
+            ),
+            CAPABILITY_DENIED,
+        );
+    }
+
+    #[test]
+    fn unknown_instance_fails() {
+        let mut handler = test_handler();
+        let response = handle(
+            &mut handler,
+            r#"{"jsonrpc": "2.0", "method": "bogus/test_zome/echo_fn", "id": 1}"#,
+        );
+        assert_eq!(
+            response,
+            json!({
+                "jsonrpc": "2.0",
+                "error": {"code": METHOD_NOT_FOUND, "message": "instance not found: bogus"},
+                "id": 1,
+            })
+        );
+    }
+
+    #[test]
+    fn failures_map_to_error_codes() {
+        let mut handler = test_handler();
+        let requests = [
+            ("not json", PARSE_ERROR),
+            (r#"{"jsonrpc": "2.0", "id": 1}"#, INVALID_REQUEST),
+            (
+                r#"{"jsonrpc": "2.0", "method": "app/echo_fn", "id": 1}"#,
+                METHOD_NOT_FOUND,
+            ),
+            (
+                r#"{"jsonrpc": "2.0", "method": "app/bogozome/echo_fn", "id": 1}"#,
+                METHOD_NOT_FOUND,
+            ),
+            (
+                r#"{"jsonrpc": "2.0", "method": "app/test_zome/bogusfn", "id": 1}"#,
+                METHOD_NOT_FOUND,
+            ),
+            (
+                r#"{"jsonrpc": "2.0", "method": "app/test_zome/fail_fn", "id": 1}"#,
+                FUNCTION_FAILED,
+            ),
+        ];
+        for &(request, code) in &requests {
+            expect_error(handle(&mut handler, request), code);
+        }
+
+        handler.container_mut().stop_all().unwrap();
+        expect_error(
+            handle(
+                &mut handler,
+                r#"{"jsonrpc": "2.0", "method": "app/test_zome/echo_fn", "id": 1}"#,
+            ),
+            INSTANCE_NOT_ACTIVE,
+        );
+    }
+}
@@ -12,31 +12,27 @@ extern crate hc_agent;
 use hc_core_api::*;
 use hc_dna::Dna;
 use hc_agent::Agent;
-use std::sync::{Arc, Mutex};
-use hc_core::context::Context;
-use hc_core::logger::SimpleLogger;
-use hc_core::persister::SimplePersister;
+use hc_core::context::ContextBuilder;
 
 // instantiate a new app
 
-// need to get to something like this:
-//let dna = hc_dna::from_package_file("mydna.hcpkg");
+// load the dna from a package file:
+//let dna = hc_dna::from_package_file(Path::new("mydna.hcpkg")).unwrap();
 
-// but for now:
-let dna = Dna::new();
+// or build one in memory:
+let mut dna = Dna::new();
+dna.name = "MyApp".to_string();
 let agent = Agent::from_string("bob");
-let context = Context {
-    agent: agent,
-    logger: Arc::new(Mutex::new(SimpleLogger {})),
-    persister: Arc::new(Mutex::new(SimplePersister::new())),
-};
-let mut hc = Holochain::new(dna,Arc::new(context)).unwrap();
+// the logger and persister default to SimpleLogger and SimplePersister
+let context = ContextBuilder::new().with_agent(agent).build().unwrap();
+// (or just use Arc::new(Context::default()) to try things out as an anonymous agent)
+let mut hc = Holochain::new(dna,context).unwrap();
 
 // start up the app
 hc.start().expect("couldn't start the app");
 
 // call a function in the app
-hc.call("some_fn");
+hc.call("some_zome", "some_fn", "", None);
 
 // get the state
 {
@@ -46,229 +42,2989 @@ hc.call("some_fn");
     // ...
 }
 
+// or take a snapshot of the state that can be held onto
+let snapshot = hc.snapshot();
+
 // stop the app
 hc.stop().expect("couldn't stop the app");
 
 ```
 */
 
+extern crate futures;
 extern crate hc_agent;
 extern crate hc_core;
 extern crate hc_dna;
+extern crate rand;
+extern crate rust_base58;
+extern crate serde;
+#[cfg(test)]
+#[macro_use]
+extern crate serde_derive;
+#[macro_use]
+extern crate serde_json;
+#[cfg(test)]
+extern crate tokio;
 
+pub mod container;
+pub mod json_rpc;
+
+use futures::executor;
+use futures::task::{self, ArcWake, Poll, Waker};
+use futures::Future;
 use hc_core::context::Context;
+use hc_core::instance::{ActionId, CallResult, PersistencePolicy};
+use hc_core::metrics::Metrics;
 use hc_dna::Dna;
-use std::sync::Arc;
+use rand::RngCore;
+use rust_base58::ToBase58;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// where an instance is in its lifecycle
+#[derive(Clone, Debug, PartialEq)]
+pub enum InstanceStatus {
+    /// loaded from a state without a dna
+    Uninitialized,
+    /// being (re)initialized from its dna
+    Initializing,
+    /// started, so calls and commits are accepted
+    Running,
+    /// started but paused, so dispatched calls and actions wait to be consumed until resumed
+    Paused,
+    /// initialized but not started, or stopped since
+    Stopped,
+    /// reinitializing or starting failed, for the given reason
+    Errored(String),
+}
+
+/// the version of the snapshot files written by `export`, which `import` only reads
+pub const SNAPSHOT_VERSION: u64 = 2;
+
+/// the largest params, in bytes, that calls accept unless set with `set_max_params_size`
+pub const DEFAULT_MAX_PARAMS_SIZE: usize = 10 * 1024 * 1024;
+
+/// custom logic run when an instance starts or stops, e.g. to open network connections
+/// or flush caches, whose error is returned from `start` or `stop`
+pub type LifecycleHook = Box<Fn(&Context) -> Result<(), HolochainError> + Send + Sync>;
 
 /// contains a Holochain application instance
 #[derive(Clone)]
 pub struct Holochain {
     instance: hc_core::instance::Instance,
     context: Arc<hc_core::context::Context>,
-    active: bool,
+    // shared so that every clone sees the same lifecycle
+    status: Arc<Mutex<InstanceStatus>>,
+    on_start: Arc<Mutex<Option<LifecycleHook>>>,
+    on_stop: Arc<Mutex<Option<LifecycleHook>>>,
+    cache: Arc<Mutex<CallCache>>,
+    max_params_size: Arc<AtomicUsize>,
+}
+
+// the functions whose results can be reused, and the results of calls to them
+#[derive(Default)]
+struct CallCache {
+    cacheable: HashSet<(String, String)>,
+    results: HashMap<(String, String, String), String>,
 }
 
+use hc_core::agent::Action::*;
+use hc_core::agent::Provenance;
+use hc_core::common::entry::Entry;
 use hc_core::error::HolochainError;
 use hc_core::nucleus::fncall;
+use hc_core::nucleus::signal::Signal;
 use hc_core::nucleus::Action::*;
+use hc_core::nucleus::CapabilityGrant;
+use hc_core::source_chain::Pair;
 use hc_core::state::Action::*;
-use hc_core::state::State;
+use hc_core::state::{Action, State, StateDiff};
 
 impl Holochain {
-    /// create a new Holochain instance
+    /// create a new Holochain instance, failing if the dna is invalid or genesis fails;
+    /// the instance logs through a copy of the context named after the dna
     pub fn new(dna: Dna, context: Arc<Context>) -> Result<Self, HolochainError> {
+        dna.validate()?;
         let mut instance = hc_core::instance::Instance::new();
-        let name = dna.name.clone();
-        let action = Nucleus(InitApplication(dna));
-        instance.dispatch(action);
-        instance.consume_next_action()?;
-        context.log(&format!("{} instantiated", name))?;
+        let context = Arc::new(context.named(&dna.name));
+        instance.set_context(context.clone());
+        initialize(&mut instance, dna, &context)?;
+        context.log("instantiated")?;
         let app = Holochain {
             instance,
             context,
-            active: false,
+            status: Arc::new(Mutex::new(InstanceStatus::Stopped)),
+            on_start: Arc::new(Mutex::new(None)),
+            on_stop: Arc::new(Mutex::new(None)),
+            cache: Arc::new(Mutex::new(CallCache::default())),
+            max_params_size: Arc::new(AtomicUsize::new(DEFAULT_MAX_PARAMS_SIZE)),
         };
         Ok(app)
     }
 
-    /// activate the Holochain instance
-    pub fn start(&mut self) -> Result<(), HolochainError> {
-        if self.active {
-            return Err(HolochainError::InstanceActive);
+    /// load a Holochain instance from the state saved by the context's persister,
+    /// failing if nothing has been saved yet (use `new` to start fresh instead)
+    /// or if the saved state was made with a different version of dna
+    pub fn load(dna: &Dna, context: Arc<Context>) -> Result<Self, HolochainError> {
+        let state = context.lock_persister().load()?;
+        let state = match state {
+            Some(state) => state,
+            None => return Err(HolochainError::new("no saved state to load")),
+        };
+        if let Some(saved_dna) = state.nucleus().dna() {
+            if saved_dna.version != dna.version {
+                return Err(HolochainError::DnaVersionMismatch {
+                    expected: dna.version.clone(),
+                    found: saved_dna.version,
+                });
+            }
         }
-        self.active = true;
+        let hc = Holochain::from_instance(hc_core::instance::Instance::from_state(state), context);
+        hc.context.log("loaded")?;
+        Ok(hc)
+    }
+
+    /// write the instance's dna, state and committed entries to a snapshot file at path,
+    /// for backing it up or moving it elsewhere with `import`
+    pub fn export(&self, path: &Path) -> Result<(), HolochainError> {
+        let state = self.instance.state();
+        let dna = match state.nucleus().dna() {
+            Some(dna) => dna,
+            None => return Err(HolochainError::new("no dna to export")),
+        };
+        // the state only holds the entries' addresses
+        let mut entries = Vec::new();
+        {
+            let entry_store = self.context.lock_entry_store();
+            for address in state.agent().entries() {
+                entries.extend(entry_store.get(address)?);
+            }
+        }
+        let snapshot = json!({
+            "version": SNAPSHOT_VERSION,
+            "dna": dna,
+            "state": state,
+            "entries": entries,
+        });
+        fs::write(path, serde_json::to_string_pretty(&snapshot)?)?;
         Ok(())
     }
 
-    /// deactivate the Holochain instance
+    /// create a Holochain instance from a snapshot file written by `export`, failing
+    /// with `HolochainError::SnapshotVersionMismatch` if it was written in another format;
+    /// the instance is stopped, and saves its state with the context's persister as usual
+    pub fn import(path: &Path, context: Arc<Context>) -> Result<Self, HolochainError> {
+        let mut snapshot: serde_json::Value = serde_json::from_str(&fs::read_to_string(path)?)?;
+        // the version is checked first, as the rest of another format may not parse
+        let version = match snapshot["version"].as_u64() {
+            Some(version) => version,
+            None => return Err(HolochainError::new("not a snapshot: it has no version")),
+        };
+        if version != SNAPSHOT_VERSION {
+            return Err(HolochainError::SnapshotVersionMismatch {
+                expected: SNAPSHOT_VERSION,
+                found: version,
+            });
+        }
+        let dna: Dna = serde_json::from_value(snapshot["dna"].take())?;
+        let state: State = serde_json::from_value(snapshot["state"].take())?;
+        let entries: Vec<Entry> = serde_json::from_value(snapshot["entries"].take())?;
+        dna.validate()?;
+        if state.nucleus().dna() != Some(dna) {
+            return Err(HolochainError::new(
+                "snapshot state was not made with its dna",
+            ));
+        }
+        {
+            let mut entry_store = context.lock_entry_store();
+            for entry in &entries {
+                entry_store.put(entry)?;
+            }
+        }
+        let hc = Holochain::from_instance(hc_core::instance::Instance::from_state(state), context);
+        hc.context.log("imported")?;
+        Ok(hc)
+    }
+
+    /// wrap an instance that has already been set up, e.g. with a known state for a test,
+    /// without initializing it; it logs through a copy of the context named after its dna
+    pub fn from_instance(mut instance: hc_core::instance::Instance, context: Arc<Context>) -> Self {
+        let state = instance.state();
+        let context = match state.nucleus().dna() {
+            Some(dna) => Arc::new(context.named(&dna.name)),
+            None => context,
+        };
+        let status = if state.nucleus().initialized() {
+            InstanceStatus::Stopped
+        } else {
+            InstanceStatus::Uninitialized
+        };
+        instance.set_context(context.clone());
+        Holochain {
+            instance,
+            context,
+            status: Arc::new(Mutex::new(status)),
+            on_start: Arc::new(Mutex::new(None)),
+            on_stop: Arc::new(Mutex::new(None)),
+            cache: Arc::new(Mutex::new(CallCache::default())),
+            max_params_size: Arc::new(AtomicUsize::new(DEFAULT_MAX_PARAMS_SIZE)),
+        }
+    }
+
+    /// activate the Holochain instance, consuming dispatched actions on a thread of their own
+    pub fn start(&mut self) -> Result<(), HolochainError> {
+        {
+            let mut status = self.lock_status();
+            if *status == InstanceStatus::Running || *status == InstanceStatus::Paused {
+                return Err(HolochainError::InstanceActive);
+            }
+            *status = InstanceStatus::Running;
+        }
+        if let Err(err) = self.instance.start_processing() {
+            *self.lock_status() = InstanceStatus::Errored(err.to_string());
+            return Err(err);
+        }
+        self.run_hook(&self.on_start)
+    }
+
+    /// deactivate the Holochain instance, first consuming any pending actions
+    /// so that they aren't lost
     pub fn stop(&mut self) -> Result<(), HolochainError> {
-        if !self.active {
-            return Err(HolochainError::InstanceNotActive);
+        {
+            // no more calls are accepted from here on
+            let mut status = self.lock_status();
+            if *status != InstanceStatus::Running && *status != InstanceStatus::Paused {
+                return Err(HolochainError::InstanceNotActive);
+            }
+            *status = InstanceStatus::Stopped;
+        }
+        if let Err(err) = self.instance.stop_processing() {
+            *self.lock_status() = InstanceStatus::Errored(err.to_string());
+            return Err(err);
+        }
+        self.instance.drain();
+        if self.instance.persistence_policy() == PersistencePolicy::OnStop {
+            self.instance.persist()?;
+        }
+        self.run_hook(&self.on_stop)
+    }
+
+    /// stop consuming dispatched actions without stopping the instance, e.g. for maintenance;
+    /// calls and actions dispatched while paused are queued until it is resumed
+    pub fn pause(&mut self) -> Result<(), HolochainError> {
+        {
+            let mut status = self.lock_status();
+            if *status != InstanceStatus::Running {
+                return Err(HolochainError::InstanceNotActive);
+            }
+            *status = InstanceStatus::Paused;
+        }
+        if let Err(err) = self.instance.stop_processing() {
+            *self.lock_status() = InstanceStatus::Errored(err.to_string());
+            return Err(err);
         }
-        self.active = false;
         Ok(())
     }
 
-    /// call a function in a zome
-    pub fn call(&mut self, fn_name: &str) -> Result<(), HolochainError> {
-        if !self.active {
-            return Err(HolochainError::InstanceNotActive);
+    /// consume dispatched actions again after `pause`, starting with those queued meanwhile
+    pub fn resume(&mut self) -> Result<(), HolochainError> {
+        {
+            let mut status = self.lock_status();
+            if *status != InstanceStatus::Paused {
+                return Err(HolochainError::new("instance is not paused"));
+            }
+            *status = InstanceStatus::Running;
         }
-        let call_data = fncall::Call::new(fn_name);
-        let action = Nucleus(Call(call_data));
-        self.instance.dispatch(action.clone());
-        self.instance.consume_next_action()
+        if let Err(err) = self.instance.start_processing() {
+            *self.lock_status() = InstanceStatus::Errored(err.to_string());
+            return Err(err);
+        }
+        Ok(())
     }
 
-    /// checks to see if an instance is active
-    pub fn active(&self) -> bool {
-        self.active
+    /// set when the state is saved with the context's persister;
+    /// it's saved after every action unless set otherwise
+    pub fn set_persistence_policy(&mut self, policy: PersistencePolicy) {
+        self.instance.set_persistence_policy(policy);
     }
 
-    /// return
-    pub fn state(&mut self) -> Result<&State, HolochainError> {
-        Ok(self.instance.state())
+    /// save the state with the context's persister now, whatever the persistence policy
+    pub fn persist(&self) -> Result<(), HolochainError> {
+        self.instance.persist()
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use hc_agent::Agent as HCAgent;
-    use hc_core::context::Context;
-    use hc_core::logger::Logger;
-    use hc_core::persister::SimplePersister;
-    use std::fmt;
-    use std::sync::{Arc, Mutex};
+    /// run hook each time the instance has started, replacing any hook set before;
+    /// if it fails, `start` returns its error but the instance is left running
+    pub fn set_on_start(&mut self, hook: LifecycleHook) {
+        *lock_hook(&self.on_start) = Some(hook);
+    }
+
+    /// run hook each time the instance has stopped, after the pending actions are consumed,
+    /// replacing any hook set before; if it fails, `stop` returns its error
+    pub fn set_on_stop(&mut self, hook: LifecycleHook) {
+        *lock_hook(&self.on_stop) = Some(hook);
+    }
 
-    #[derive(Clone)]
-    struct TestLogger {
-        log: Vec<String>,
+    fn run_hook(&self, hook: &Mutex<Option<LifecycleHook>>) -> Result<(), HolochainError> {
+        match *lock_hook(hook) {
+            Some(ref hook) => hook(&self.context),
+            None => Ok(()),
+        }
     }
 
-    impl Logger for TestLogger {
-        fn log(&mut self, msg: String) {
-            self.log.push(msg);
+    /// stop the instance if it is active and reinitialize it from the same dna,
+    /// clearing its state but keeping its context and registered functions;
+    /// the instance is left stopped, ready to be started again
+    pub fn restart(&mut self) -> Result<(), HolochainError> {
+        let dna = match self.instance.state().nucleus().dna() {
+            Some(dna) => dna,
+            None => return Err(HolochainError::new("no dna to restart from")),
+        };
+        if self.active() || self.paused() {
+            self.stop()?;
+        }
+        *self.lock_status() = InstanceStatus::Initializing;
+        let mut instance = self.instance.fresh();
+        if let Err(err) = initialize(&mut instance, dna, &self.context) {
+            *self.lock_status() = InstanceStatus::Errored(err.to_string());
+            return Err(err);
         }
+        self.instance.adopt(&instance);
+        *self.lock_status() = InstanceStatus::Stopped;
+        self.context.log("restarted")?;
+        Ok(())
     }
 
-    // trying to get a way to print out what has been logged for tests without a read function.
-    // this currently fails
-    impl fmt::Debug for TestLogger {
-        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-            write!(f, "{:?}", self.log[0])
+    /// clear the state back to how it was just after initialization, with the dna
+    /// and its seed entries but no other entries or call results, without running genesis again;
+    /// only allowed while the instance is stopped
+    pub fn reset_state(&mut self) -> Result<(), HolochainError> {
+        match self.status() {
+            InstanceStatus::Stopped => {}
+            InstanceStatus::Running | InstanceStatus::Paused => {
+                return Err(HolochainError::InstanceActive)
+            }
+            _ => return Err(HolochainError::new("only a stopped instance can be reset")),
         }
+        let dna = match self.instance.state().nucleus().dna() {
+            Some(dna) => dna,
+            None => return Err(HolochainError::new("no dna to reset to")),
+        };
+        let mut instance = self.instance.fresh();
+        init_application(&mut instance, dna)?;
+        self.instance.adopt(&instance);
+        self.context.log("state reset")?;
+        Ok(())
     }
 
-    fn test_context(agent: hc_agent::Agent) -> (Arc<Context>, Arc<Mutex<TestLogger>>) {
-        let logger = Arc::new(Mutex::new(TestLogger { log: Vec::new() }));
-        (
-            Arc::new(Context {
-                agent: agent,
-                logger: logger.clone(),
-                persister: Arc::new(Mutex::new(SimplePersister::new())),
-            }),
-            logger,
-        )
+    /// rebuild the state by replaying recorded actions, e.g. another instance's history,
+    /// onto a fresh state, without running the recorded calls again;
+    /// only possible while the instance isn't active
+    pub fn replay(&mut self, actions: &[Action]) -> Result<(), HolochainError> {
+        if self.active() {
+            return Err(HolochainError::InstanceActive);
+        }
+        let replayed = self.instance.replay(actions)?;
+        *self.lock_status() = if replayed.state().nucleus().initialized() {
+            InstanceStatus::Stopped
+        } else {
+            InstanceStatus::Uninitialized
+        };
+        self.instance.adopt(&replayed);
+        self.context.log("replayed")?;
+        Ok(())
     }
 
-    #[test]
-    fn can_instantiate() {
-        let mut dna = Dna::new();
-        dna.name = "TestApp".to_string();
-        let agent = HCAgent::from_string("bob");
-        let (context, test_logger) = test_context(agent.clone());
-        let result = Holochain::new(dna.clone(), context.clone());
-        let hc = result.clone().unwrap();
-        assert!(!hc.active);
-        assert_eq!(hc.context.agent, agent);
-        let test_logger = test_logger.lock().unwrap();
-        assert_eq!(format!("{:?}", *test_logger), "\"TestApp instantiated\"");
+    /// the number of actions waiting to be consumed
+    pub fn pending_actions(&self) -> usize {
+        self.instance.pending_actions().len()
+    }
+
+    /// wait until every dispatched action has been consumed, failing with
+    /// `HolochainError::Timeout` if some are still pending once timeout has passed
+    pub fn wait_for_idle(&self, timeout: Duration) -> Result<(), HolochainError> {
+        if self.instance.wait_for_idle(timeout) {
+            Ok(())
+        } else {
+            Err(HolochainError::Timeout)
+        }
+    }
 
+    /// call a function in a zome, passing its parameters as a json string
+    /// (use an empty string for functions that take no parameters)
+    /// and returning the function's result; functions that aren't public
+    /// need a token granting one of the capabilities that declare them;
+    /// concurrent calls on clones each consume exactly the call they dispatched;
+    /// while the instance is paused, the call waits to be consumed until it is resumed
+    pub fn call(
+        &mut self,
+        zome: &str,
+        fn_name: &str,
+        params: &str,
+        cap_token: Option<&str>,
+    ) -> Result<String, HolochainError> {
+        if !self.active() && !self.paused() {
+            return Err(HolochainError::InstanceNotActive);
+        }
+        self.validate_call(zome, fn_name, params, cap_token)?;
+        let key = (zome.to_string(), fn_name.to_string(), params.to_string());
+        let cacheable = {
+            let cache = self.lock_cache();
+            if let Some(result) = cache.results.get(&key) {
+                return Ok(result.clone());
+            }
+            cache.cacheable.contains(&(key.0.clone(), key.1.clone()))
+        };
+        let call_data = fncall::Call::new(zome, fn_name).with_parameters(params);
+        let result = if self.paused() {
+            match executor::block_on(self.instance.dispatch_call(call_data)?) {
+                Ok(result) => result,
+                Err(_) => Err(HolochainError::new("function call returned no result")),
+            }
+        } else {
+            self.instance.dispatch_and_take(call_data)?
+        };
+        // failures aren't cached, so that calls that failed are tried again
         match result {
-            Ok(hc) => {
-                assert_eq!(hc.instance.state().nucleus().dna(), Some(dna));
+            Ok(ref value) if cacheable => {
+                self.lock_cache().results.insert(key, value.clone());
             }
-            Err(_) => assert!(false),
+            _ => {}
+        }
+        result
+    }
+
+    /// set the largest params, in bytes, that calls accept (`DEFAULT_MAX_PARAMS_SIZE` unless set)
+    pub fn set_max_params_size(&mut self, bytes: usize) {
+        self.max_params_size.store(bytes, Ordering::SeqCst);
+    }
+
+    pub fn max_params_size(&self) -> usize {
+        self.max_params_size.load(Ordering::SeqCst)
+    }
+
+    // rejects params over the size limit before they are dispatched
+    fn check_params_size(&self, params: &str) -> Result<(), HolochainError> {
+        let limit = self.max_params_size();
+        if params.len() > limit {
+            return Err(HolochainError::PayloadTooLarge {
+                size: params.len(),
+                limit,
+            });
+        }
+        Ok(())
+    }
+
+    /// mark whether calls to fn_name in zome can be answered with the result of an earlier
+    /// call with the same params, which only read-only functions should be
+    pub fn set_cacheable(&mut self, zome: &str, fn_name: &str, cacheable: bool) {
+        let mut cache = self.lock_cache();
+        let function = (zome.to_string(), fn_name.to_string());
+        if cacheable {
+            cache.cacheable.insert(function);
+        } else {
+            cache.cacheable.remove(&function);
+            cache
+                .results
+                .retain(|key, _| key.0 != zome || key.1 != fn_name);
+        }
+    }
+
+    /// forget every cached call result, e.g. once the entries they read have changed
+    pub fn clear_cache(&mut self) {
+        self.lock_cache().results.clear();
+    }
+
+    fn lock_cache(&self) -> MutexGuard<'_, CallCache> {
+        self.cache
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// make several calls of public functions, given as (zome, function, params),
+    /// each consumed before the next is made, returning each call's own result
+    /// so that one call failing doesn't stop the rest from being made
+    pub fn call_many(
+        &mut self,
+        calls: Vec<(String, String, String)>,
+    ) -> Vec<Result<String, HolochainError>> {
+        calls
+            .iter()
+            .map(|(zome, fn_name, params)| self.call(zome, fn_name, params, None))
+            .collect()
+    }
+
+    /// call a function in a zome like `call`, passing params and reading its result as json;
+    /// functions that declare a signature are only called with params naming its inputs
+    pub fn call_json<P: Serialize, R: DeserializeOwned>(
+        &mut self,
+        zome: &str,
+        fn_name: &str,
+        params: &P,
+        cap_token: Option<&str>,
+    ) -> Result<R, HolochainError> {
+        let params = serde_json::to_value(params)?;
+        self.check_params(zome, fn_name, &params)?;
+        let result = self.call(zome, fn_name, &params.to_string(), cap_token)?;
+        Ok(serde_json::from_str(&result)?)
+    }
+
+    // checks that params are an object with exactly the inputs the function's signature
+    // declares, if it declares one
+    fn check_params(
+        &self,
+        zome: &str,
+        fn_name: &str,
+        params: &serde_json::Value,
+    ) -> Result<(), HolochainError> {
+        let dna = match self.dna() {
+            Some(dna) => dna,
+            None => return Ok(()),
         };
+        let signature = match dna.function_signature(zome, fn_name) {
+            Some(signature) => signature,
+            None => return Ok(()),
+        };
+        let mut expected: Vec<&str> = signature
+            .inputs
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        let mut found: Vec<&str> = match params.as_object() {
+            Some(params) => params.keys().map(|name| name.as_str()).collect(),
+            None => {
+                return Err(HolochainError::ValidationFailed(format!(
+                    "{} in zome {} takes named params, found {}",
+                    fn_name, zome, params
+                )))
+            }
+        };
+        expected.sort();
+        found.sort();
+        if expected != found {
+            return Err(HolochainError::ValidationFailed(format!(
+                "{} in zome {} takes params [{}], found [{}]",
+                fn_name,
+                zome,
+                expected.join(", "),
+                found.join(", ")
+            )));
+        }
+        Ok(())
     }
 
-    #[test]
-    fn can_start_and_stop() {
-        let dna = Dna::new();
-        let agent = HCAgent::from_string("bob");
-        let (context, _) = test_context(agent.clone());
-        let mut hc = Holochain::new(dna.clone(), context).unwrap();
-        assert!(!hc.clone().active());
+    /// call a function in a zome without waiting for it, returning a future that
+    /// completes with the function's result once the call has been consumed,
+    /// and that can be cancelled until then
+    pub fn call_async(
+        &mut self,
+        zome: &str,
+        fn_name: &str,
+        params: &str,
+        cap_token: Option<&str>,
+    ) -> CallHandle {
+        if !self.active() && !self.paused() {
+            return CallHandle::rejected(HolochainError::InstanceNotActive);
+        }
+        if let Err(err) = self.validate_call(zome, fn_name, params, cap_token) {
+            return CallHandle::rejected(err);
+        }
+        let call_data = fncall::Call::new(zome, fn_name).with_parameters(params);
+        let call_id = call_data.id();
+        // the call is consumed on the processing thread
+        match self.instance.dispatch_call(call_data) {
+            Ok(result) => CallHandle {
+                call: CallHandleState::Dispatched {
+                    instance: self.instance.clone(),
+                    call_id,
+                    result,
+                },
+                waker: None,
+            },
+            Err(err) => CallHandle::rejected(err),
+        }
+    }
 
-        // stop when not active returns error
-        let result = hc.stop();
-        match result {
-            Err(HolochainError::InstanceNotActive) => assert!(true),
-            Ok(_) => assert!(false),
-            Err(_) => assert!(false),
+    /// call a function in a zome like `call`, but give up with `HolochainError::Timeout`
+    /// if the function hasn't returned within timeout; the call itself isn't cancelled
+    pub fn call_with_timeout(
+        &mut self,
+        zome: &str,
+        fn_name: &str,
+        params: &str,
+        cap_token: Option<&str>,
+        timeout: Duration,
+    ) -> Result<String, HolochainError> {
+        if !self.active() && !self.paused() {
+            return Err(HolochainError::InstanceNotActive);
         }
+        self.validate_call(zome, fn_name, params, cap_token)?;
+        let call_data = fncall::Call::new(zome, fn_name).with_parameters(params);
+        // the call is consumed on the processing thread while this one waits
+        let call_result = self.instance.dispatch_call(call_data)?;
+        wait_for(call_result, timeout)
+    }
 
-        let result = hc.start();
-        match result {
-            Ok(_) => assert!(true),
-            Err(_) => assert!(false),
+    /// preview calling a public function in a zome: the function is run against a copy
+    /// of the state, returning its result and what the call would change, but the
+    /// instance's own state is left as it was
+    pub fn call_dry_run(
+        &self,
+        zome: &str,
+        fn_name: &str,
+        params: &str,
+    ) -> Result<(String, StateDiff), HolochainError> {
+        if !self.active() {
+            return Err(HolochainError::InstanceNotActive);
         }
-        assert!(hc.active());
+        self.validate_call(zome, fn_name, params, None)?;
+        let call_data = fncall::Call::new(zome, fn_name).with_parameters(params);
+        let before = self.instance.state();
+        let after = self.instance.dry_run(Nucleus(Call(call_data.clone())))?;
+        match after.nucleus().call_result(&call_data) {
+            Some(result) => Ok((result?, before.diff(&after))),
+            None => Err(HolochainError::new("function call returned no result")),
+        }
+    }
 
-        // start when active returns error
-        let result = hc.start();
-        match result {
-            Err(HolochainError::InstanceActive) => assert!(true),
-            Ok(_) => assert!(false),
-            Err(_) => assert!(false),
+    /// preview dispatching a raw action, returning what it would change
+    /// without changing the instance's own state
+    pub fn dry_run(&self, action: Action) -> Result<StateDiff, HolochainError> {
+        if !self.active() {
+            return Err(HolochainError::InstanceNotActive);
         }
+        let before = self.instance.state();
+        let after = self.instance.dry_run(action)?;
+        Ok(before.diff(&after))
+    }
 
-        let result = hc.stop();
-        match result {
-            Ok(_) => assert!(true),
-            Err(_) => assert!(false),
+    /// dispatch a raw action to the instance, e.g. to replay a log or inject network events,
+    /// returning its id; it is consumed on the processing thread like any other action
+    /// (once resumed, if the instance is paused)
+    pub fn dispatch_action(&mut self, action: Action) -> Result<ActionId, HolochainError> {
+        if !self.active() && !self.paused() {
+            return Err(HolochainError::InstanceNotActive);
         }
-        assert!(!hc.active());
+        self.instance.dispatch(action)
     }
 
-    #[test]
-    fn can_call() {
-        let dna = Dna::new();
-        let agent = HCAgent::from_string("bob");
-        let (context, _) = test_context(agent.clone());
-        let mut hc = Holochain::new(dna.clone(), context).unwrap();
-        let result = hc.call("bogusfn");
-        match result {
-            Err(HolochainError::InstanceNotActive) => assert!(true),
-            Ok(_) => assert!(false),
-            Err(_) => assert!(false),
+    /// grant access to a set of functions, returning the token that calls can present;
+    /// the grant is kept in the state, so it lasts as long as the state does
+    pub fn grant_capability(&mut self, functions: Vec<String>) -> Result<String, HolochainError> {
+        let mut bytes = [0u8; 32];
+        rand::rngs::OsRng.fill_bytes(&mut bytes);
+        let token = bytes.to_base58();
+        let grant = CapabilityGrant::new(functions);
+        self.instance
+            .dispatch_and_consume(Nucleus(GrantCapability(token.clone(), grant)))?;
+        Ok(token)
+    }
+
+    /// the grant made with token, if there is one
+    pub fn capability_grant(&self, token: &str) -> Option<CapabilityGrant> {
+        self.instance.state().nucleus().capability_grant(token)
+    }
+
+    // the checks every way of calling a function makes before the call is dispatched
+    fn validate_call(
+        &self,
+        zome: &str,
+        fn_name: &str,
+        params: &str,
+        cap_token: Option<&str>,
+    ) -> Result<(), HolochainError> {
+        self.check_params_size(params)?;
+        if !self.nucleus_initialized() {
+            return Err(HolochainError::NotInitialized);
         }
+        self.check_call(zome, fn_name, cap_token)
+    }
 
-        hc.start().expect("couldn't start");
+    // checks that there is a dna, that it declares the function, and that the token grants
+    // access to it if it isn't public, before a call to it is dispatched
+    fn check_call(
+        &self,
+        zome: &str,
+        fn_name: &str,
+        cap_token: Option<&str>,
+    ) -> Result<(), HolochainError> {
+        let dna = match self.instance.state().nucleus().dna() {
+            Some(dna) => dna,
+            None => return Err(HolochainError::NotInitialized),
+        };
+        let zome_def = match dna.get_zome(zome) {
+            Some(zome_def) => zome_def,
+            None => return Err(HolochainError::ZomeNotFound(zome.to_string())),
+        };
+        if !zome_def.has_function(fn_name) {
+            return Err(HolochainError::ZomeFunctionNotFound {
+                zome: zome.to_string(),
+                function: fn_name.to_string(),
+            });
+        }
+        if zome_def.is_public(fn_name) {
+            return Ok(());
+        }
+        let granted = match cap_token.and_then(|token| self.capability_grant(token)) {
+            Some(grant) => grant.allows(fn_name),
+            None => false,
+        };
+        if !granted {
+            return Err(HolochainError::CapabilityDenied {
+                zome: zome.to_string(),
+                function: fn_name.to_string(),
+            });
+        }
+        Ok(())
+    }
 
-        // always returns not implemented error for now!
-        let result = hc.call("bogusfn");
-        match result {
-            Err(HolochainError::NotImplemented) => assert!(true),
-            Ok(_) => assert!(true),
-            Err(_) => assert!(false),
+    /// commit an entry of the given type, returning the address it is stored at
+    pub fn commit_entry(
+        &mut self,
+        entry_type: &str,
+        content: &str,
+    ) -> Result<String, HolochainError> {
+        if !self.active() {
+            return Err(HolochainError::InstanceNotActive);
+        }
+        let entry = Entry::new(entry_type, content);
+        let address = entry.address();
+        let action = match self.provenance(content) {
+            Some(provenance) => SignedCommit(entry, provenance),
+            None => Commit(entry),
         };
+        self.instance.dispatch_and_consume(Agent(action))?;
+        Ok(address)
     }
 
-    #[test]
-    fn can_get_state() {
-        let dna = Dna::new();
-        let agent = HCAgent::from_string("bob");
-        let (context, _) = test_context(agent.clone());
-        let mut hc = Holochain::new(dna.clone(), context).unwrap();
+    /// commit new_content as the new version of the entry at old_address, returning
+    /// the new entry's address; the old entry can still be got at its own address
+    pub fn update_entry(
+        &mut self,
+        old_address: &str,
+        new_content: &str,
+    ) -> Result<String, HolochainError> {
+        if !self.active() {
+            return Err(HolochainError::InstanceNotActive);
+        }
+        let old_entry = self
+            .get_entry(old_address)?
+            .ok_or_else(|| HolochainError::EntryNotFound(old_address.to_string()))?;
+        let entry = Entry::new(&old_entry.entry_type(), new_content);
+        let address = entry.address();
+        self.instance.dispatch_and_consume(Agent(UpdateEntry {
+            old_address: old_address.to_string(),
+            entry,
+            provenance: self.provenance(new_content),
+        }))?;
+        Ok(address)
+    }
 
-        let result = hc.state();
-        match result {
-            Ok(state) => {
-                assert_eq!(state.nucleus().dna(), Some(dna));
+    /// remove the entry at address, so that getting it returns None
+    pub fn remove_entry(&mut self, address: &str) -> Result<(), HolochainError> {
+        if !self.active() {
+            return Err(HolochainError::InstanceNotActive);
+        }
+        self.instance
+            .dispatch_and_consume(Agent(RemoveEntry(address.to_string())))?;
+        Ok(())
+    }
+
+    // agents with a key pair sign what they commit
+    fn provenance(&self, content: &str) -> Option<Provenance> {
+        let agent = &self.context.agent;
+        match (agent.public_key(), agent.sign(content.as_bytes())) {
+            (Some(public_key), Some(signature)) => {
+                Some(Provenance::new(&agent.address(), public_key, signature))
             }
-            Err(_) => assert!(false),
-        };
+            _ => None,
+        }
+    }
+
+    /// check that the entry at address is signed by the agent that committed it,
+    /// which it isn't if it is missing, unsigned or has been tampered with
+    pub fn verify_entry(&self, address: &str) -> Result<bool, HolochainError> {
+        let agent = self.instance.state().agent();
+        match self.context.lock_entry_store().get(address)? {
+            Some(ref entry) => Ok(agent.verify_entry(address, entry)),
+            None => Ok(false),
+        }
+    }
+
+    /// register a native validator that commits of entries of `entry_type` must pass
+    pub fn register_validator<F>(&mut self, entry_type: &str, validator: F)
+    where
+        F: Fn(&Entry) -> Result<(), String> + Send + Sync + 'static,
+    {
+        self.instance.register_validator(entry_type, validator);
+    }
+
+    /// get the committed entry stored at address from the context's entry store,
+    /// which works whether or not the instance is active
+    pub fn get_entry(&self, address: &str) -> Result<Option<Entry>, HolochainError> {
+        // the store may outlive the state, e.g. when it is reset, so the state says
+        // which entries are committed and not removed
+        let agent = self.instance.state().agent();
+        if agent.has_entry(address) {
+            return self.context.lock_entry_store().get(address);
+        }
+        // entries this agent never committed may have been published by others
+        if !agent.entries().contains(address) {
+            return Ok(self.context.lock_dht().and_then(|dht| dht.fetch(address)));
+        }
+        Ok(None)
+    }
+
+    /// the agent's source chain, in the order entries were committed,
+    /// each linking to the one committed before it
+    pub fn chain(&self) -> Vec<Pair> {
+        self.instance.state().agent().chain()
+    }
+
+    /// the address of the entry that the entry at address was updated to, if it was updated
+    pub fn get_updated_address(&self, address: &str) -> Option<String> {
+        self.instance.state().agent().updated_address(address)
+    }
+
+    /// all the committed entries of entry_type, in the order they were committed
+    pub fn query(&self, entry_type: &str) -> Result<Vec<Entry>, HolochainError> {
+        let agent = self.instance.state().agent();
+        let stored = self.context.lock_entry_store().all_of_type(entry_type)?;
+        Ok(stored
+            .into_iter()
+            .filter(|entry| agent.has_entry(&entry.address()))
+            .collect())
+    }
+
+    /// link the entry at base to the entry at target with tag,
+    /// failing if either of them hasn't been committed
+    pub fn link_entries(
+        &mut self,
+        base: &str,
+        target: &str,
+        tag: &str,
+    ) -> Result<(), HolochainError> {
+        if !self.active() {
+            return Err(HolochainError::InstanceNotActive);
+        }
+        self.instance.dispatch_and_consume(Agent(LinkEntries {
+            base: base.to_string(),
+            target: target.to_string(),
+            tag: tag.to_string(),
+        }))?;
+        Ok(())
+    }
+
+    /// the addresses of the entries linked to from base with tag, in the order they were linked
+    pub fn get_links(&self, base: &str, tag: &str) -> Vec<String> {
+        self.instance.state().agent().links(base, tag)
+    }
+
+    /// register a native function that calls to `fn_name` in `zome` will run
+    pub fn register_function<F>(&mut self, zome: &str, fn_name: &str, function: F)
+    where
+        F: Fn(&str) -> Result<String, HolochainError> + Send + Sync + 'static,
+    {
+        self.instance.register_function(zome, fn_name, function);
+    }
+
+    /// the address of the agent this instance acts as
+    pub fn agent_address(&self) -> String {
+        self.context.agent.address()
+    }
+
+    /// the counters kept about the calls this instance has served
+    pub fn metrics(&self) -> Metrics {
+        self.instance.metrics()
+    }
+
+    /// a hash of the current state, which is the same for instances with the same state
+    pub fn state_hash(&self) -> String {
+        self.instance.state_hash()
+    }
+
+    /// the name of the instantiated dna
+    pub fn name(&self) -> Option<String> {
+        self.instance.state().nucleus().dna().map(|dna| dna.name)
+    }
+
+    /// the instantiated dna, with its zomes, properties and version
+    pub fn dna(&self) -> Option<Dna> {
+        self.instance.state().nucleus().dna()
+    }
+
+    /// where the instance is in its lifecycle
+    pub fn status(&self) -> InstanceStatus {
+        self.lock_status().clone()
+    }
+
+    /// whether the nucleus has finished initializing the application from its dna,
+    /// which it may have whether or not the instance is active
+    pub fn nucleus_initialized(&self) -> bool {
+        self.instance.state().nucleus().initialized()
+    }
+
+    /// checks to see if an instance is active, which it is while running
+    pub fn active(&self) -> bool {
+        *self.lock_status() == InstanceStatus::Running
+    }
+
+    /// whether the instance is paused, so that dispatched actions wait to be consumed
+    pub fn paused(&self) -> bool {
+        *self.lock_status() == InstanceStatus::Paused
+    }
+
+    // nothing can panic while the status is locked, so a poisoned lock is still consistent
+    fn lock_status(&self) -> MutexGuard<'_, InstanceStatus> {
+        self.status
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// observe the state, which is sent each time it changes
+    pub fn observe(&mut self) -> Receiver<State> {
+        self.instance.subscribe()
+    }
+
+    /// receive the signals emitted by zome functions, for the container to forward;
+    /// a call's signals are sent after its result is stored
+    pub fn signals(&mut self) -> Receiver<Signal> {
+        self.instance.subscribe_signals()
+    }
+
+    /// the actions applied to the instance so far, oldest first
+    pub fn history(&self) -> Vec<Action> {
+        self.instance.history()
+    }
+
+    /// the current state; the same as `snapshot`, which it never fails to take,
+    /// but returning a Result as it always has
+    pub fn state(&self) -> Result<State, HolochainError> {
+        Ok(self.snapshot())
+    }
+
+    /// a copy of the current state that isn't affected by later actions
+    /// (cheap, as the state shares its unchanged parts)
+    pub fn snapshot(&self) -> State {
+        self.instance.state()
+    }
+
+    /// a handle that can read this instance's state but not change it
+    pub fn read_only(&self) -> ReadOnlyHolochain {
+        ReadOnlyHolochain { hc: self.clone() }
+    }
+}
+
+/// a view of a Holochain instance that can read its state but not call, commit, start
+/// or stop it, e.g. for a metrics exporter or a UI; it shares the instance, so reads are live
+#[derive(Clone)]
+pub struct ReadOnlyHolochain {
+    hc: Holochain,
+}
+
+impl ReadOnlyHolochain {
+    /// the current state, as `Holochain::state`
+    pub fn state(&self) -> Result<State, HolochainError> {
+        Ok(self.hc.snapshot())
+    }
+
+    /// a copy of the current state that isn't affected by later actions
+    pub fn snapshot(&self) -> State {
+        self.hc.snapshot()
+    }
+
+    /// get the committed entry stored at address
+    pub fn get_entry(&self, address: &str) -> Result<Option<Entry>, HolochainError> {
+        self.hc.get_entry(address)
+    }
+
+    /// all the committed entries of entry_type, in the order they were committed
+    pub fn query(&self, entry_type: &str) -> Result<Vec<Entry>, HolochainError> {
+        self.hc.query(entry_type)
+    }
+
+    /// the name of the instantiated dna
+    pub fn name(&self) -> Option<String> {
+        self.hc.name()
+    }
+}
+
+/// a call made with `call_async`, which is a future of the function's result
+pub struct CallHandle {
+    call: CallHandleState,
+    // woken on cancelling, in case the handle is being polled
+    waker: Option<Waker>,
+}
+
+enum CallHandleState {
+    // the call was never dispatched
+    Rejected(HolochainError),
+    Dispatched {
+        instance: hc_core::instance::Instance,
+        call_id: usize,
+        result: CallResult,
+    },
+    Cancelled,
+}
+
+impl CallHandle {
+    fn rejected(err: HolochainError) -> Self {
+        CallHandle {
+            call: CallHandleState::Rejected(err),
+            waker: None,
+        }
+    }
+
+    /// give up on the call, resolving the handle with `HolochainError::Cancelled`;
+    /// the call isn't run if it hasn't been consumed yet, and its result is discarded if it has
+    pub fn cancel(&mut self) {
+        if let CallHandleState::Dispatched {
+            ref instance,
+            call_id,
+            ..
+        } = self.call
+        {
+            instance.cancel_call(call_id);
+        }
+        self.call = CallHandleState::Cancelled;
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl Future for CallHandle {
+    type Output = Result<String, HolochainError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut task::Context) -> Poll<Self::Output> {
+        let handle = self.get_mut();
+        match handle.call {
+            CallHandleState::Rejected(ref err) => Poll::Ready(Err(err.clone())),
+            CallHandleState::Dispatched { ref mut result, .. } => {
+                handle.waker = Some(cx.waker().clone());
+                match Pin::new(result).poll(cx) {
+                    Poll::Ready(Ok(result)) => Poll::Ready(result),
+                    Poll::Ready(Err(_)) => {
+                        Poll::Ready(Err(HolochainError::new("function call returned no result")))
+                    }
+                    Poll::Pending => Poll::Pending,
+                }
+            }
+            CallHandleState::Cancelled => Poll::Ready(Err(HolochainError::Cancelled)),
+        }
+    }
+}
+
+// wakes the thread waiting on a call result once it is ready
+struct Unparker(thread::Thread);
+
+impl ArcWake for Unparker {
+    fn wake_by_ref(arc_self: &Arc<Self>) {
+        arc_self.0.unpark();
+    }
+}
+
+// blocks until the call result arrives or timeout has passed
+fn wait_for(mut call_result: CallResult, timeout: Duration) -> Result<String, HolochainError> {
+    let waker = task::waker(Arc::new(Unparker(thread::current())));
+    let mut cx = task::Context::from_waker(&waker);
+    let deadline = Instant::now() + timeout;
+    loop {
+        match Pin::new(&mut call_result).poll(&mut cx) {
+            Poll::Ready(Ok(result)) => return result,
+            Poll::Ready(Err(_)) => {
+                return Err(HolochainError::new("function call returned no result"))
+            }
+            Poll::Pending => {}
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            return Err(HolochainError::Timeout);
+        }
+        thread::park_timeout(deadline - now);
+    }
+}
+
+// a hook that panicked can still be replaced or run again
+fn lock_hook(hook: &Mutex<Option<LifecycleHook>>) -> MutexGuard<'_, Option<LifecycleHook>> {
+    hook.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+// initializes the instance's application with the dna, then runs genesis
+fn initialize(
+    instance: &mut hc_core::instance::Instance,
+    dna: Dna,
+    context: &Context,
+) -> Result<(), HolochainError> {
+    init_application(instance, dna)?;
+    context.genesis()
+}
+
+// initializes the instance's application with the dna and commits the dna's seed entries,
+// which must pass validation like any other commit
+fn init_application(
+    instance: &mut hc_core::instance::Instance,
+    dna: Dna,
+) -> Result<(), HolochainError> {
+    let seed_entries = dna.seed_entries.clone();
+    instance.dispatch_and_consume(Nucleus(InitApplication(dna)))?;
+    for seed in seed_entries {
+        let entry = Entry::new(&seed.entry_type, &seed.content);
+        instance.dispatch_and_consume(Agent(Commit(entry)))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hc_agent::Agent as HCAgent;
+    use hc_core::context::Context;
+    use hc_core::test_utils::{mock_context, mock_dna, SpyPersister};
+    use hc_dna::zome::capabilities::Membrane;
+    use hc_dna::zome::ZomeBuilder;
+    use hc_dna::SeedEntry;
+    use std::sync::{Arc, Mutex};
+
+    // a valid dna with a single zome called "test_zome"
+    fn test_dna() -> Dna {
+        let mut dna = Dna::new();
+        dna.name = "TestApp".to_string();
+        let zome = ZomeBuilder::new()
+            .with_capability("public", Membrane::Public)
+            .with_function("public", "stub_fn")
+            .with_function("public", "fail_fn")
+            .with_function("public", "bogusfn")
+            .with_capability("admin", Membrane::Agent)
+            .with_function("admin", "admin_fn")
+            .with_capability("other", Membrane::Agent)
+            .with_function("other", "other_fn")
+            .build();
+        dna.add_zome("test_zome", zome).unwrap();
+        dna
+    }
+
+    #[test]
+    fn can_instantiate() {
+        let mut dna = Dna::new();
+        dna.name = "TestApp".to_string();
+        let agent = HCAgent::from_string("bob");
+        let (context, test_logger) = mock_context(agent.clone());
+        let result = Holochain::new(dna.clone(), context.clone());
+        let hc = result.clone().unwrap();
+        assert!(!hc.active());
+        assert_eq!(hc.context.agent, agent);
+        let test_logger = test_logger.lock().unwrap();
+        assert_eq!(
+            test_logger.dump(),
+            vec!["[TestApp] instantiated".to_string()]
+        );
+
+        match result {
+            Ok(hc) => {
+                assert_eq!(hc.instance.state().nucleus().dna(), Some(dna));
+            }
+            Err(_) => assert!(false),
+        };
+    }
+
+    #[test]
+    fn can_instantiate_with_default_context() {
+        // an unnamed dna isn't valid, whatever the context
+        let mut dna = Dna::new();
+        dna.name = "TestApp".to_string();
+        let hc = Holochain::new(dna, Arc::new(Context::default())).unwrap();
+        assert_eq!(hc.context.agent, HCAgent::from_string("anonymous"));
+        assert_eq!(hc.status(), InstanceStatus::Stopped);
+    }
+
+    #[test]
+    fn can_instantiate_with_genesis() {
+        let agent = HCAgent::from_string("bob");
+        let (context, test_logger) = mock_context(agent.clone());
+        let context = Arc::new(Context {
+            genesis: Some(Arc::new(|context: &Context| context.log("genesis ran"))),
+            ..(*context).clone()
+        });
+
+        let hc = Holochain::new(test_dna(), context);
+        assert!(hc.is_ok());
+        assert_eq!(
+            test_logger.lock().unwrap().dump(),
+            vec![
+                "[TestApp] genesis ran".to_string(),
+                "[TestApp] instantiated".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn failing_genesis_fails_instantiation() {
+        let agent = HCAgent::from_string("bob");
+        let (context, test_logger) = mock_context(agent.clone());
+        let context = Arc::new(Context {
+            genesis: Some(Arc::new(|_: &Context| Err(HolochainError::new("borked")))),
+            ..(*context).clone()
+        });
+
+        let result = Holochain::new(test_dna(), context);
+        match result {
+            Err(err) => assert_eq!(err, HolochainError::new("borked")),
+            Ok(_) => assert!(false),
+        };
+        assert!(test_logger.lock().unwrap().dump().is_empty());
+    }
+
+    #[test]
+    fn cannot_instantiate_invalid_dna() {
+        let agent = HCAgent::from_string("bob");
+        let (context, test_logger) = mock_context(agent.clone());
+
+        let result = Holochain::new(Dna::new(), context.clone());
+        match result {
+            Err(HolochainError::InvalidDna(_)) => assert!(true),
+            Ok(_) => assert!(false),
+            Err(_) => assert!(false),
+        };
+        assert!(test_logger.lock().unwrap().dump().is_empty());
+
+        assert!(Holochain::new(test_dna(), context).is_ok());
+    }
+
+    #[test]
+    fn can_load() {
+        let mut dna = Dna::new();
+        dna.name = "TestApp".to_string();
+        let agent = HCAgent::from_string("bob");
+        let (context, test_logger) = mock_context(agent.clone());
+
+        // nothing has been saved yet
+        match Holochain::load(&dna, context.clone()) {
+            Err(HolochainError::ErrorGeneric(_)) => assert!(true),
+            Ok(_) => assert!(false),
+            Err(_) => assert!(false),
+        };
+
+        let hc = Holochain::new(dna.clone(), context.clone()).unwrap();
+        context
+            .persister
+            .lock()
+            .unwrap()
+            .save(&hc.state().unwrap())
+            .unwrap();
+
+        let loaded = Holochain::load(&dna, context.clone()).unwrap();
+        assert!(!loaded.active());
+        assert_eq!(loaded.state().unwrap(), hc.state().unwrap());
+        assert_eq!(loaded.state().unwrap().nucleus().dna(), Some(dna));
+        let test_logger = test_logger.lock().unwrap();
+        assert_eq!(
+            test_logger.dump(),
+            vec![
+                "[TestApp] instantiated".to_string(),
+                "[TestApp] loaded".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn loading_different_dna_version_fails() {
+        let mut dna = test_dna();
+        dna.version = "0.1.0".to_string();
+        let (context, _) = mock_context(HCAgent::from_string("bob"));
+        Holochain::new(dna.clone(), context.clone()).unwrap();
+
+        let mut upgraded = dna.clone();
+        upgraded.version = "0.2.0".to_string();
+        match Holochain::load(&upgraded, context.clone()) {
+            Err(HolochainError::DnaVersionMismatch { expected, found }) => {
+                assert_eq!(expected, "0.2.0");
+                assert_eq!(found, "0.1.0");
+            }
+            Ok(_) => assert!(false),
+            Err(_) => assert!(false),
+        };
+
+        assert!(Holochain::load(&dna, context).is_ok());
+    }
+
+    #[test]
+    fn can_wrap_instance() {
+        let (context, test_logger) = mock_context(HCAgent::from_string("bob"));
+        let mut instance = hc_core::instance::Instance::new();
+        instance
+            .dispatch_and_consume(Nucleus(InitApplication(test_dna())))
+            .unwrap();
+        let entry = Entry::new("person", "alice");
+        instance
+            .dispatch_and_consume(Agent(Commit(entry.clone())))
+            .unwrap();
+
+        let mut hc = Holochain::from_instance(instance, context);
+        assert_eq!(hc.status(), InstanceStatus::Stopped);
+        assert_eq!(hc.name(), Some("TestApp".to_string()));
+        assert!(hc.snapshot().agent().has_entry(&entry.address()));
+        // wrapping doesn't initialize the instance again
+        assert_eq!(hc.history().len(), 2);
+        assert!(test_logger.lock().unwrap().dump().is_empty());
+
+        hc.start().expect("couldn't start");
+        assert!(hc.call("test_zome", "stub_fn", "", None).is_err());
+        assert_eq!(hc.history().len(), 5);
+
+        let hc = Holochain::from_instance(
+            hc_core::instance::Instance::new(),
+            mock_context(HCAgent::from_string("bob")).0,
+        );
+        assert_eq!(hc.status(), InstanceStatus::Uninitialized);
+    }
+
+    #[test]
+    fn state_is_saved_automatically() {
+        let dna = test_dna();
+        let agent = HCAgent::from_string("bob");
+        let (context, _) = mock_context(agent.clone());
+        let mut hc = Holochain::new(dna.clone(), context.clone()).unwrap();
+        hc.start().expect("couldn't start");
+        let address = hc.commit_entry("test_type", "some content").unwrap();
+
+        let loaded = Holochain::load(&dna, context).unwrap();
+        assert_eq!(loaded.snapshot(), hc.snapshot());
+        assert!(loaded.get_entry(&address).unwrap().is_some());
+    }
+
+    #[test]
+    fn can_export_and_import() {
+        let path =
+            std::env::temp_dir().join(format!("hc_core_api_snapshot_{}.json", std::process::id()));
+        let (context, _) = mock_context(HCAgent::from_string("bob"));
+        let mut hc = Holochain::new(test_dna(), context).unwrap();
+        hc.start().expect("couldn't start");
+        let address = hc.commit_entry("post", "hello").unwrap();
+        hc.export(&path).expect("couldn't export");
+
+        let (context, test_logger) = mock_context(HCAgent::from_string("bob"));
+        let imported = Holochain::import(&path, context).unwrap();
+        assert_eq!(imported.snapshot(), hc.snapshot());
+        assert_eq!(imported.dna(), hc.dna());
+        assert_eq!(imported.status(), InstanceStatus::Stopped);
+        assert_eq!(
+            imported.get_entry(&address),
+            Ok(Some(Entry::new("post", "hello")))
+        );
+        assert_eq!(
+            test_logger.lock().unwrap().dump(),
+            vec!["[TestApp] imported".to_string()]
+        );
+
+        // snapshots written in another format aren't imported
+        let mut snapshot: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        snapshot["version"] = json!(SNAPSHOT_VERSION + 1);
+        std::fs::write(&path, snapshot.to_string()).unwrap();
+        let (context, _) = mock_context(HCAgent::from_string("bob"));
+        match Holochain::import(&path, context) {
+            Err(err) => assert_eq!(
+                err,
+                HolochainError::SnapshotVersionMismatch {
+                    expected: SNAPSHOT_VERSION,
+                    found: SNAPSHOT_VERSION + 1,
+                }
+            ),
+            Ok(_) => assert!(false),
+        };
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn saves_expected_state() {
+        let spy = Arc::new(Mutex::new(SpyPersister::new()));
+        let context = Arc::new(Context {
+            persister: spy.clone(),
+            ..(*mock_context(HCAgent::from_string("bob")).0).clone()
+        });
+        let mut hc = Holochain::new(test_dna(), context).unwrap();
+        // initializing is saved too
+        assert_eq!(spy.lock().unwrap().save_count(), 1);
+
+        hc.start().expect("couldn't start");
+        let address = hc.commit_entry("test_type", "some content").unwrap();
+        let saved_states = spy.lock().unwrap().saved_states();
+        assert_eq!(saved_states.len(), 2);
+        assert_eq!(saved_states[1], hc.snapshot());
+        assert!(saved_states[1].agent().has_entry(&address));
+    }
+
+    // a Holochain saving with a spy persister, which has saved initializing
+    fn spied_holochain() -> (Holochain, Arc<Mutex<SpyPersister>>) {
+        let spy = Arc::new(Mutex::new(SpyPersister::new()));
+        let context = Arc::new(Context {
+            persister: spy.clone(),
+            ..(*mock_context(HCAgent::from_string("bob")).0).clone()
+        });
+        let hc = Holochain::new(test_dna(), context).unwrap();
+        assert_eq!(spy.lock().unwrap().save_count(), 1);
+        (hc, spy)
+    }
+
+    #[test]
+    fn can_save_only_on_stop() {
+        let (mut hc, spy) = spied_holochain();
+        hc.set_persistence_policy(PersistencePolicy::OnStop);
+        hc.start().expect("couldn't start");
+        hc.commit_entry("test_type", "some content").unwrap();
+        hc.commit_entry("test_type", "other content").unwrap();
+        assert_eq!(spy.lock().unwrap().save_count(), 1);
+
+        hc.stop().expect("couldn't stop");
+        let saved_states = spy.lock().unwrap().saved_states();
+        assert_eq!(saved_states.len(), 2);
+        assert_eq!(saved_states[1], hc.snapshot());
+    }
+
+    #[test]
+    fn can_save_only_manually() {
+        let (mut hc, spy) = spied_holochain();
+        hc.set_persistence_policy(PersistencePolicy::Manual);
+        hc.start().expect("couldn't start");
+        hc.commit_entry("test_type", "some content").unwrap();
+        hc.stop().expect("couldn't stop");
+        assert_eq!(spy.lock().unwrap().save_count(), 1);
+
+        hc.persist().unwrap();
+        let saved_states = spy.lock().unwrap().saved_states();
+        assert_eq!(saved_states.len(), 2);
+        assert_eq!(saved_states[1], hc.snapshot());
+    }
+
+    #[test]
+    fn can_instantiate_with_mock_helpers() {
+        let (context, logger) = mock_context(HCAgent::from_string("bob"));
+        let mut hc = Holochain::new(mock_dna("MockApp"), context).unwrap();
+        hc.register_function("test_zome", "test_fn", |_| Ok("mocked".to_string()));
+        hc.start().expect("couldn't start");
+        assert_eq!(
+            hc.call("test_zome", "test_fn", "", None),
+            Ok("mocked".to_string())
+        );
+        assert_eq!(
+            logger.lock().unwrap().dump(),
+            vec!["[MockApp] instantiated".to_string()]
+        );
+    }
+
+    #[test]
+    fn can_get_name() {
+        let mut dna = Dna::new();
+        dna.name = "TestApp".to_string();
+        let agent = HCAgent::from_string("bob");
+        let (context, _) = mock_context(agent.clone());
+        let hc = Holochain::new(dna.clone(), context).unwrap();
+        assert_eq!(hc.name(), Some("TestApp".to_string()));
+    }
+
+    #[test]
+    fn can_get_dna() {
+        let dna = test_dna();
+        let (context, _) = mock_context(HCAgent::from_string("bob"));
+        let hc = Holochain::new(dna.clone(), context).unwrap();
+        assert_eq!(hc.dna(), Some(dna));
+
+        let hc = Holochain::from_instance(hc_core::instance::Instance::new(), hc.context.clone());
+        assert_eq!(hc.dna(), None);
+    }
+
+    #[test]
+    fn can_start_and_stop() {
+        let dna = test_dna();
+        let agent = HCAgent::from_string("bob");
+        let (context, _) = mock_context(agent.clone());
+        let mut hc = Holochain::new(dna.clone(), context).unwrap();
+        assert!(!hc.clone().active());
+
+        // stop when not active returns error
+        let result = hc.stop();
+        match result {
+            Err(HolochainError::InstanceNotActive) => assert!(true),
+            Ok(_) => assert!(false),
+            Err(_) => assert!(false),
+        }
+
+        let result = hc.start();
+        match result {
+            Ok(_) => assert!(true),
+            Err(_) => assert!(false),
+        }
+        assert!(hc.active());
+
+        // start when active returns error
+        let result = hc.start();
+        match result {
+            Err(HolochainError::InstanceActive) => assert!(true),
+            Ok(_) => assert!(false),
+            Err(_) => assert!(false),
+        }
+
+        let result = hc.stop();
+        match result {
+            Ok(_) => assert!(true),
+            Err(_) => assert!(false),
+        }
+        assert!(!hc.active());
+    }
+
+    #[test]
+    fn lifecycle_hooks_fire_once_per_start_and_stop() {
+        let (context, _) = mock_context(HCAgent::from_string("bob"));
+        let mut hc = Holochain::new(test_dna(), context).unwrap();
+        let events = Arc::new(Mutex::new(Vec::new()));
+        for &(event, on_start) in &[("started", true), ("stopped", false)] {
+            let events = events.clone();
+            let hook: LifecycleHook = Box::new(move |context: &Context| {
+                let name = context.instance_name.clone().unwrap_or_default();
+                events.lock().unwrap().push(format!("{} {}", name, event));
+                Ok(())
+            });
+            if on_start {
+                hc.set_on_start(hook);
+            } else {
+                hc.set_on_stop(hook);
+            }
+        }
+        assert!(hc.stop().is_err());
+        assert!(events.lock().unwrap().is_empty());
+
+        hc.start().expect("couldn't start");
+        assert!(hc.start().is_err());
+        assert_eq!(*events.lock().unwrap(), vec!["TestApp started"]);
+
+        hc.stop().expect("couldn't stop");
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec!["TestApp started", "TestApp stopped"]
+        );
+    }
+
+    #[test]
+    fn lifecycle_hook_errors_are_returned() {
+        let (context, _) = mock_context(HCAgent::from_string("bob"));
+        let mut hc = Holochain::new(test_dna(), context).unwrap();
+        hc.set_on_start(Box::new(|_| Err(HolochainError::new("no network"))));
+        hc.set_on_stop(Box::new(|_| Err(HolochainError::new("flush failed"))));
+
+        assert_eq!(hc.start(), Err(HolochainError::new("no network")));
+        assert!(hc.active());
+        assert_eq!(hc.stop(), Err(HolochainError::new("flush failed")));
+        assert!(!hc.active());
+    }
+
+    #[test]
+    fn status_follows_lifecycle() {
+        let dna = test_dna();
+        let agent = HCAgent::from_string("bob");
+        let (context, _) = mock_context(agent.clone());
+        let mut hc = Holochain::new(dna.clone(), context).unwrap();
+        assert_eq!(hc.status(), InstanceStatus::Stopped);
+
+        hc.start().expect("couldn't start");
+        assert_eq!(hc.status(), InstanceStatus::Running);
+        assert!(hc.active());
+
+        hc.stop().expect("couldn't stop");
+        assert_eq!(hc.status(), InstanceStatus::Stopped);
+        assert!(!hc.active());
+    }
+
+    #[test]
+    fn failed_restart_is_errored() {
+        // genesis only succeeds the first time it runs
+        let runs = Arc::new(::std::sync::atomic::AtomicUsize::new(0));
+        let context = hc_core::context::ContextBuilder::new()
+            .with_agent(HCAgent::from_string("bob"))
+            .with_genesis(move |_| {
+                if runs.fetch_add(1, ::std::sync::atomic::Ordering::SeqCst) == 0 {
+                    Ok(())
+                } else {
+                    Err(HolochainError::new("genesis failed"))
+                }
+            })
+            .build()
+            .unwrap();
+        let mut hc = Holochain::new(test_dna(), context).unwrap();
+        hc.start().expect("couldn't start");
+
+        assert_eq!(hc.restart(), Err(HolochainError::new("genesis failed")));
+        assert_eq!(
+            hc.status(),
+            InstanceStatus::Errored("genesis failed".to_string())
+        );
+        assert!(!hc.active());
+    }
+
+    #[test]
+    fn loading_state_without_dna_is_uninitialized() {
+        let agent = HCAgent::from_string("bob");
+        let (context, _) = mock_context(agent.clone());
+        context
+            .persister
+            .lock()
+            .unwrap()
+            .save(&State::new())
+            .unwrap();
+        let hc = Holochain::load(&test_dna(), context).unwrap();
+        assert_eq!(hc.status(), InstanceStatus::Uninitialized);
+    }
+
+    #[test]
+    fn calls_need_an_initialized_nucleus() {
+        let (context, _) = mock_context(HCAgent::from_string("bob"));
+        let hc = Holochain::new(test_dna(), context.clone()).unwrap();
+        assert!(hc.nucleus_initialized());
+        assert!(!hc.active());
+
+        let mut hc = Holochain::from_instance(hc_core::instance::Instance::new(), context);
+        assert!(!hc.nucleus_initialized());
+        hc.start().expect("couldn't start");
+        assert_eq!(
+            hc.call("test_zome", "stub_fn", "", None),
+            Err(HolochainError::NotInitialized)
+        );
+        assert_eq!(
+            executor::block_on(hc.call_async("test_zome", "stub_fn", "", None)),
+            Err(HolochainError::NotInitialized)
+        );
+        let timeout = Duration::from_secs(5);
+        assert_eq!(
+            hc.call_with_timeout("test_zome", "stub_fn", "", None, timeout),
+            Err(HolochainError::NotInitialized)
+        );
+        assert_eq!(hc.pending_actions(), 0);
+    }
+
+    #[test]
+    fn calling_without_dna_fails() {
+        let (context, _) = mock_context(HCAgent::from_string("bob"));
+        context
+            .persister
+            .lock()
+            .unwrap()
+            .save(&State::new())
+            .unwrap();
+        let mut hc = Holochain::load(&test_dna(), context).unwrap();
+        hc.start().expect("couldn't start");
+        assert_eq!(
+            hc.call("test_zome", "stub_fn", "", None),
+            Err(HolochainError::NotInitialized)
+        );
+    }
+
+    #[test]
+    fn pausing_queues_calls_until_resumed() {
+        let dna = test_dna();
+        let agent = HCAgent::from_string("bob");
+        let (context, _) = mock_context(agent.clone());
+        let mut hc = Holochain::new(dna.clone(), context).unwrap();
+        hc.register_function("test_zome", "stub_fn", |_| Ok("done".to_string()));
+        assert_eq!(hc.pause(), Err(HolochainError::InstanceNotActive));
+        hc.start().expect("couldn't start");
+
+        hc.pause().expect("couldn't pause");
+        assert_eq!(hc.status(), InstanceStatus::Paused);
+        assert!(!hc.active());
+        assert_eq!(hc.start(), Err(HolochainError::InstanceActive));
+
+        let handle = hc.call_async("test_zome", "stub_fn", "", None);
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(hc.pending_actions(), 1);
+
+        hc.resume().expect("couldn't resume");
+        assert_eq!(hc.status(), InstanceStatus::Running);
+        assert_eq!(executor::block_on(handle), Ok("done".to_string()));
+        assert_eq!(hc.pending_actions(), 0);
+        assert_eq!(
+            hc.resume(),
+            Err(HolochainError::new("instance is not paused"))
+        );
+
+        hc.pause().expect("couldn't pause");
+        hc.stop().expect("couldn't stop");
+        assert_eq!(hc.status(), InstanceStatus::Stopped);
+    }
+
+    #[test]
+    fn can_wait_for_dispatched_actions_to_be_consumed() {
+        let (context, _) = mock_context(HCAgent::from_string("bob"));
+        let mut hc = Holochain::new(test_dna(), context).unwrap();
+        hc.start().expect("couldn't start");
+        assert_eq!(hc.wait_for_idle(Duration::from_secs(5)), Ok(()));
+
+        let calls: Vec<fncall::Call> = (0..5)
+            .map(|i| fncall::Call::new("test_zome", "bogusfn").with_parameters(&i.to_string()))
+            .collect();
+        for call in &calls {
+            hc.dispatch_action(Nucleus(Call(call.clone()))).unwrap();
+        }
+        assert_eq!(hc.wait_for_idle(Duration::from_secs(5)), Ok(()));
+        assert_eq!(hc.pending_actions(), 0);
+        for call in &calls {
+            assert!(hc.snapshot().nucleus().call_result(call).is_some());
+        }
+
+        // nothing consumes actions while paused
+        hc.pause().expect("couldn't pause");
+        hc.dispatch_action(Nucleus(Call(calls[0].clone()))).unwrap();
+        assert_eq!(
+            hc.wait_for_idle(Duration::from_millis(50)),
+            Err(HolochainError::Timeout)
+        );
+        hc.resume().expect("couldn't resume");
+        assert_eq!(hc.wait_for_idle(Duration::from_secs(5)), Ok(()));
+    }
+
+    #[test]
+    fn stop_consumes_pending_actions() {
+        let dna = test_dna();
+        let agent = HCAgent::from_string("bob");
+        let (context, _) = mock_context(agent.clone());
+        let mut hc = Holochain::new(dna.clone(), context).unwrap();
+        hc.start().expect("couldn't start");
+        assert_eq!(hc.pending_actions(), 0);
+
+        // the processing thread may or may not have got to them before stopping
+        let call = fncall::Call::new("test_zome", "bogusfn");
+        hc.instance.dispatch(Nucleus(Call(call.clone()))).unwrap();
+        hc.instance.dispatch(Nucleus(Call(call.clone()))).unwrap();
+
+        hc.stop().expect("couldn't stop");
+        assert_eq!(hc.pending_actions(), 0);
+        assert!(hc.snapshot().nucleus().call_result(&call).is_some());
+    }
+
+    #[test]
+    fn can_restart() {
+        let dna = test_dna();
+        let agent = HCAgent::from_string("bob");
+        let (context, test_logger) = mock_context(agent.clone());
+        let mut hc = Holochain::new(dna.clone(), context).unwrap();
+        hc.register_function("test_zome", "stub_fn", |_| Ok("stubbed result".to_string()));
+        hc.start().expect("couldn't start");
+        hc.call("test_zome", "stub_fn", "", None).unwrap();
+        let address = hc.commit_entry("test_type", "some content").unwrap();
+        let call = match hc.history()[2] {
+            Nucleus(ReturnResult(ref call, _)) => call.clone(),
+            _ => panic!("call result was not recorded"),
+        };
+
+        // clones share the instance, so they see the restart too
+        let clone = hc.clone();
+        hc.restart().expect("couldn't restart");
+        assert!(!hc.active());
+        assert_eq!(hc.snapshot().nucleus().dna(), Some(dna));
+        assert_eq!(hc.snapshot().nucleus().call_result(&call), None);
+        assert_eq!(hc.get_entry(&address), Ok(None));
+        assert_eq!(clone.get_entry(&address), Ok(None));
+        assert_eq!(clone.snapshot(), hc.snapshot());
+        assert_eq!(
+            test_logger.lock().unwrap().dump(),
+            vec![
+                "[TestApp] instantiated".to_string(),
+                "[TestApp] restarted".to_string(),
+            ]
+        );
+
+        // functions are still registered after restarting
+        hc.start().expect("couldn't start");
+        let result = hc.call("test_zome", "stub_fn", "", None);
+        assert_eq!(result, Ok("stubbed result".to_string()));
+    }
+
+    #[test]
+    fn can_reset_state() {
+        let dna = test_dna();
+        let agent = HCAgent::from_string("bob");
+        let (context, test_logger) = mock_context(agent.clone());
+        let mut hc = Holochain::new(dna.clone(), context).unwrap();
+        hc.start().expect("couldn't start");
+        let address = hc.commit_entry("test_type", "some content").unwrap();
+
+        assert_eq!(hc.reset_state(), Err(HolochainError::InstanceActive));
+        hc.pause().expect("couldn't pause");
+        assert_eq!(hc.reset_state(), Err(HolochainError::InstanceActive));
+        assert!(hc.get_entry(&address).unwrap().is_some());
+
+        // clones and views share the instance, so they see the reset too
+        let clone = hc.clone();
+        let view = hc.read_only();
+        hc.stop().expect("couldn't stop");
+        hc.reset_state().expect("couldn't reset");
+        assert!(!hc.active());
+        assert_eq!(hc.get_entry(&address).unwrap(), None);
+        assert_eq!(clone.get_entry(&address).unwrap(), None);
+        assert_eq!(view.snapshot(), hc.snapshot());
+        assert_eq!(hc.snapshot().nucleus().dna(), Some(dna));
+        assert_eq!(hc.history().len(), 1);
+        assert_eq!(
+            test_logger.lock().unwrap().dump().last(),
+            Some(&"[TestApp] state reset".to_string())
+        );
+
+        // and can be started again
+        hc.start().expect("couldn't start");
+        hc.commit_entry("test_type", "some content").unwrap();
+        assert!(hc.get_entry(&address).unwrap().is_some());
+    }
+
+    #[test]
+    fn reset_state_keeps_seed_entries() {
+        let mut dna = test_dna();
+        dna.seed_entries = vec![SeedEntry::new("post", "hello")];
+        let (context, _) = mock_context(HCAgent::from_string("bob"));
+        let mut hc = Holochain::new(dna, context).unwrap();
+        hc.start().expect("couldn't start");
+        hc.commit_entry("post", "goodbye").unwrap();
+        hc.stop().expect("couldn't stop");
+
+        hc.reset_state().expect("couldn't reset");
+        assert_eq!(hc.query("post"), Ok(vec![Entry::new("post", "hello")]));
+    }
+
+    #[test]
+    fn clones_share_active_state() {
+        let dna = test_dna();
+        let agent = HCAgent::from_string("bob");
+        let (context, _) = mock_context(agent.clone());
+        let mut hc = Holochain::new(dna.clone(), context).unwrap();
+        let mut other = hc.clone();
+
+        hc.start().expect("couldn't start");
+        assert!(other.active());
+        match other.start() {
+            Err(HolochainError::InstanceActive) => assert!(true),
+            Ok(_) => assert!(false),
+            Err(_) => assert!(false),
+        };
+
+        other.stop().expect("couldn't stop");
+        assert!(!hc.active());
+    }
+
+    #[test]
+    fn can_call() {
+        let dna = test_dna();
+        let agent = HCAgent::from_string("bob");
+        let (context, _) = mock_context(agent.clone());
+        let mut hc = Holochain::new(dna.clone(), context).unwrap();
+        let result = hc.call("test_zome", "bogusfn", "", None);
+        match result {
+            Err(HolochainError::InstanceNotActive) => assert!(true),
+            Ok(_) => assert!(false),
+            Err(_) => assert!(false),
+        }
+
+        hc.start().expect("couldn't start");
+
+        // always returns not implemented error for now!
+        let result = hc.call("test_zome", "bogusfn", "", None);
+        match result {
+            Err(HolochainError::NotImplemented) => assert!(true),
+            Ok(_) => assert!(true),
+            Err(_) => assert!(false),
+        };
+    }
+
+    #[test]
+    fn can_call_and_get_result() {
+        let dna = test_dna();
+        let agent = HCAgent::from_string("bob");
+        let (context, _) = mock_context(agent.clone());
+        let mut hc = Holochain::new(dna.clone(), context).unwrap();
+        hc.register_function("test_zome", "stub_fn", |_| Ok("stubbed result".to_string()));
+        hc.start().expect("couldn't start");
+
+        let result = hc.call("test_zome", "stub_fn", "", None);
+        assert_eq!(result, Ok("stubbed result".to_string()));
+    }
+
+    #[test]
+    fn can_call_many() {
+        let dna = test_dna();
+        let agent = HCAgent::from_string("bob");
+        let (context, _) = mock_context(agent.clone());
+        let mut hc = Holochain::new(dna.clone(), context).unwrap();
+        hc.register_function("test_zome", "stub_fn", |params| {
+            Ok(format!("got {}", params))
+        });
+        hc.register_function("test_zome", "fail_fn", |_| {
+            Err(HolochainError::new("borked"))
+        });
+        let call = |fn_name: &str, params: &str| {
+            (
+                "test_zome".to_string(),
+                fn_name.to_string(),
+                params.to_string(),
+            )
+        };
+        let calls = vec![
+            call("stub_fn", "1"),
+            call("fail_fn", ""),
+            call("stub_fn", "2"),
+        ];
+        assert_eq!(
+            hc.call_many(calls.clone()),
+            vec![Err(HolochainError::InstanceNotActive); 3]
+        );
+
+        hc.start().expect("couldn't start");
+        let results = hc.call_many(calls);
+        assert_eq!(results[0], Ok("got 1".to_string()));
+        match results[1] {
+            Err(HolochainError::ZomeFunctionFailed { ref reason, .. }) => {
+                assert_eq!(reason, "borked")
+            }
+            _ => assert!(false),
+        }
+        assert_eq!(results[2], Ok("got 2".to_string()));
+        assert!(hc.call_many(Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn can_call_async() {
+        let dna = test_dna();
+        let agent = HCAgent::from_string("bob");
+        let (context, _) = mock_context(agent.clone());
+        let mut hc = Holochain::new(dna.clone(), context).unwrap();
+        hc.register_function("test_zome", "stub_fn", |params| {
+            Ok(format!("stubbed {}", params))
+        });
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+
+        let result = runtime.block_on(hc.call_async("test_zome", "stub_fn", "result", None));
+        assert_eq!(result, Err(HolochainError::InstanceNotActive));
+
+        hc.start().expect("couldn't start");
+        let result = runtime.block_on(hc.call_async("test_zome", "stub_fn", "result", None));
+        assert_eq!(result, Ok("stubbed result".to_string()));
+
+        let result = runtime.block_on(hc.call_async("bogozome", "bogusfn", "", None));
+        assert_eq!(
+            result,
+            Err(HolochainError::ZomeNotFound("bogozome".to_string()))
+        );
+    }
+
+    #[test]
+    fn call_with_timeout_gives_up_on_slow_functions() {
+        let (context, _) = mock_context(HCAgent::from_string("bob"));
+        let mut hc = Holochain::new(test_dna(), context).unwrap();
+        hc.register_function("test_zome", "stub_fn", |params| {
+            let delay = params.parse().unwrap_or(0);
+            thread::sleep(Duration::from_millis(delay));
+            Ok(format!("stubbed {}", params))
+        });
+        let timeout = Duration::from_millis(20);
+        assert_eq!(
+            hc.call_with_timeout("test_zome", "stub_fn", "200", None, timeout),
+            Err(HolochainError::InstanceNotActive)
+        );
+
+        hc.start().expect("couldn't start");
+        assert_eq!(
+            hc.call_with_timeout("test_zome", "stub_fn", "1000", None, timeout),
+            Err(HolochainError::Timeout)
+        );
+        // the slow call is still running, but neither it nor the calls queued behind it
+        // keep the state from being read or later callers from giving up in time
+        let started = Instant::now();
+        assert_eq!(
+            hc.call_with_timeout("test_zome", "stub_fn", "0", None, timeout),
+            Err(HolochainError::Timeout)
+        );
+        hc.snapshot();
+        assert!(started.elapsed() < Duration::from_millis(500));
+
+        // the slow call still finishes before the next one is consumed
+        let timeout = Duration::from_secs(5);
+        assert_eq!(
+            hc.call_with_timeout("test_zome", "stub_fn", "0", None, timeout),
+            Ok("stubbed 0".to_string())
+        );
+    }
+
+    #[test]
+    fn interleaved_calls_get_their_own_results() {
+        let (context, _) = mock_context(HCAgent::from_string("bob"));
+        let mut hc = Holochain::new(test_dna(), context).unwrap();
+        hc.register_function("test_zome", "stub_fn", |params| {
+            Ok(format!("got {}", params))
+        });
+        hc.start().expect("couldn't start");
+
+        // a call dispatched from another clone is waiting when this one is made
+        let mut other = hc.clone();
+        let pending = other.call_async("test_zome", "stub_fn", "first", None);
+        assert_eq!(
+            hc.call("test_zome", "stub_fn", "second", None),
+            Ok("got second".to_string())
+        );
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        assert_eq!(runtime.block_on(pending), Ok("got first".to_string()));
+
+        let threads: Vec<_> = (0..4)
+            .map(|n| {
+                let mut hc = hc.clone();
+                thread::spawn(move || {
+                    for i in 0..20 {
+                        let params = format!("{}-{}", n, i);
+                        assert_eq!(
+                            hc.call("test_zome", "stub_fn", &params, None),
+                            Ok(format!("got {}", params))
+                        );
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().expect("a caller got another's result");
+        }
+    }
+
+    #[test]
+    fn oversized_params_are_rejected() {
+        let (context, _) = mock_context(HCAgent::from_string("bob"));
+        let mut hc = Holochain::new(test_dna(), context).unwrap();
+        hc.register_function("test_zome", "stub_fn", |params| {
+            Ok(params.len().to_string())
+        });
+        hc.start().expect("couldn't start");
+        assert_eq!(hc.max_params_size(), DEFAULT_MAX_PARAMS_SIZE);
+
+        hc.set_max_params_size(10);
+        assert_eq!(
+            hc.call("test_zome", "stub_fn", &"x".repeat(10), None),
+            Ok("10".to_string())
+        );
+        let pending = hc.pending_actions();
+        assert_eq!(
+            hc.call("test_zome", "stub_fn", &"x".repeat(11), None),
+            Err(HolochainError::PayloadTooLarge {
+                size: 11,
+                limit: 10,
+            })
+        );
+        assert_eq!(hc.pending_actions(), pending);
+
+        // however the function is called
+        let timeout = Duration::from_secs(5);
+        assert_eq!(
+            hc.call_with_timeout("test_zome", "stub_fn", &"x".repeat(11), None, timeout),
+            Err(HolochainError::PayloadTooLarge {
+                size: 11,
+                limit: 10,
+            })
+        );
+        assert_eq!(
+            executor::block_on(hc.call_async("test_zome", "stub_fn", &"x".repeat(11), None)),
+            Err(HolochainError::PayloadTooLarge {
+                size: 11,
+                limit: 10,
+            })
+        );
+        assert_eq!(hc.pending_actions(), pending);
+    }
+
+    #[test]
+    fn signals_are_received_after_call_results() {
+        use hc_core::nucleus::signal::emit_signal;
+
+        let (context, _) = mock_context(HCAgent::from_string("bob"));
+        let mut hc = Holochain::new(test_dna(), context).unwrap();
+        hc.register_function("test_zome", "stub_fn", |params| {
+            emit_signal("posted", params);
+            Ok("done".to_string())
+        });
+        let signals = hc.signals();
+        hc.start().expect("couldn't start");
+
+        assert_eq!(
+            hc.call("test_zome", "stub_fn", r#"{"title":"hello"}"#, None),
+            Ok("done".to_string())
+        );
+        assert_eq!(
+            signals.recv_timeout(Duration::from_secs(5)),
+            Ok(Signal::new("posted", r#"{"title":"hello"}"#))
+        );
+        assert!(signals.try_recv().is_err());
+
+        // the signal is recorded after the call's result has been taken
+        let history = hc.history();
+        match history[history.len() - 3] {
+            Nucleus(ReturnResult(_, Ok(ref result))) => assert_eq!(result, "done"),
+            _ => assert!(false),
+        }
+        assert_eq!(
+            history.last(),
+            Some(&Nucleus(EmitSignal(Signal::new(
+                "posted",
+                r#"{"title":"hello"}"#
+            ))))
+        );
+    }
+
+    #[test]
+    fn dry_runs_leave_state_unchanged() {
+        let (context, _) = mock_context(HCAgent::from_string("bob"));
+        let mut hc = Holochain::new(test_dna(), context).unwrap();
+        let runs = Arc::new(Mutex::new(0));
+        let counter = runs.clone();
+        hc.register_function("test_zome", "stub_fn", move |params| {
+            *counter.lock().unwrap() += 1;
+            Ok(format!("got {}", params))
+        });
+        let entry = Entry::new("post", "hello");
+        assert_eq!(
+            hc.dry_run(Agent(Commit(entry.clone()))),
+            Err(HolochainError::InstanceNotActive)
+        );
+        hc.start().expect("couldn't start");
+        let before = hc.snapshot();
+
+        let diff = hc.dry_run(Agent(Commit(entry.clone()))).unwrap();
+        assert_eq!(diff.added_entries, vec![entry.address()]);
+        assert_eq!(hc.get_entry(&entry.address()), Ok(None));
+
+        let (result, diff) = hc.call_dry_run("test_zome", "stub_fn", "1").unwrap();
+        assert_eq!(result, "got 1");
+        assert_eq!(diff.nucleus_changes, vec!["call_results".to_string()]);
+        assert_eq!(*runs.lock().unwrap(), 1);
+
+        assert_eq!(hc.snapshot(), before);
+        assert_eq!(
+            hc.call_dry_run("test_zome", "admin_fn", ""),
+            Err(HolochainError::CapabilityDenied {
+                zome: "test_zome".to_string(),
+                function: "admin_fn".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn cacheable_calls_run_once() {
+        let (context, _) = mock_context(HCAgent::from_string("bob"));
+        let mut hc = Holochain::new(test_dna(), context).unwrap();
+        let runs = Arc::new(Mutex::new(0));
+        let counter = runs.clone();
+        hc.register_function("test_zome", "stub_fn", move |params| {
+            *counter.lock().unwrap() += 1;
+            Ok(format!("got {}", params))
+        });
+        hc.start().expect("couldn't start");
+        let mut call = |params| hc.call("test_zome", "stub_fn", params, None);
+
+        // calls aren't cached unless their function is marked cacheable
+        assert_eq!(call("1"), Ok("got 1".to_string()));
+        assert_eq!(call("1"), Ok("got 1".to_string()));
+        assert_eq!(*runs.lock().unwrap(), 2);
+
+        hc.set_cacheable("test_zome", "stub_fn", true);
+        let mut call = |params| hc.call("test_zome", "stub_fn", params, None);
+        assert_eq!(call("1"), Ok("got 1".to_string()));
+        assert_eq!(call("1"), Ok("got 1".to_string()));
+        assert_eq!(*runs.lock().unwrap(), 3);
+        assert_eq!(call("2"), Ok("got 2".to_string()));
+        assert_eq!(*runs.lock().unwrap(), 4);
+
+        hc.clear_cache();
+        assert_eq!(
+            hc.call("test_zome", "stub_fn", "1", None),
+            Ok("got 1".to_string())
+        );
+        assert_eq!(*runs.lock().unwrap(), 5);
+    }
+
+    #[test]
+    fn can_cancel_async_call() {
+        let (context, _) = mock_context(HCAgent::from_string("bob"));
+        let mut hc = Holochain::new(test_dna(), context).unwrap();
+        hc.register_function("test_zome", "stub_fn", |params| {
+            let delay = params.parse().unwrap_or(0);
+            thread::sleep(Duration::from_millis(delay));
+            Ok(format!("stubbed {}", params))
+        });
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        hc.start().expect("couldn't start");
+
+        let mut handle = hc.call_async("test_zome", "stub_fn", "200", None);
+        handle.cancel();
+        assert_eq!(runtime.block_on(handle), Err(HolochainError::Cancelled));
+
+        // calls that weren't cancelled are unaffected
+        let handle = hc.call_async("test_zome", "stub_fn", "0", None);
+        assert_eq!(runtime.block_on(handle), Ok("stubbed 0".to_string()));
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Point {
+        x: i32,
+        y: i32,
+    }
+
+    #[derive(Serialize)]
+    struct Polar {
+        r: i32,
+        theta: i32,
+    }
+
+    #[test]
+    fn can_call_with_json() {
+        let (context, _) = mock_context(HCAgent::from_string("bob"));
+        let mut hc = Holochain::new(test_dna(), context).unwrap();
+        hc.register_function("test_zome", "stub_fn", |params| Ok(params.to_string()));
+        hc.start().expect("couldn't start");
+
+        let point = Point { x: 1, y: -2 };
+        let result: Result<Point, HolochainError> =
+            hc.call_json("test_zome", "stub_fn", &point, None);
+        assert_eq!(result, Ok(point));
+
+        // results that don't deserialize as the expected type fail
+        let result: Result<Point, HolochainError> =
+            hc.call_json("test_zome", "stub_fn", &"not a point", None);
+        match result {
+            Err(HolochainError::SerializationError(_)) => assert!(true),
+            Ok(_) => assert!(false),
+            Err(_) => assert!(false),
+        };
+    }
+
+    #[test]
+    fn json_params_must_match_declared_signature() {
+        use hc_dna::zome::capabilities::FunctionSignature;
+
+        let mut dna = test_dna();
+        let signature = FunctionSignature {
+            inputs: vec![
+                ("x".to_string(), "integer".to_string()),
+                ("y".to_string(), "integer".to_string()),
+            ],
+            output: "Point".to_string(),
+        };
+        dna.zomes[0].capabilities[0].fn_declarations[0].signature = Some(signature.clone());
+        let (context, _) = mock_context(HCAgent::from_string("bob"));
+        let mut hc = Holochain::new(dna, context).unwrap();
+        hc.register_function("test_zome", "stub_fn", |params| Ok(params.to_string()));
+        hc.start().expect("couldn't start");
+        assert_eq!(
+            hc.dna().unwrap().function_signature("test_zome", "stub_fn"),
+            Some(&signature)
+        );
+
+        let point = Point { x: 1, y: -2 };
+        let result: Result<Point, HolochainError> =
+            hc.call_json("test_zome", "stub_fn", &point, None);
+        assert_eq!(result, Ok(point));
+
+        let result: Result<Point, HolochainError> =
+            hc.call_json("test_zome", "stub_fn", &Polar { r: 1, theta: 0 }, None);
+        assert_eq!(
+            result,
+            Err(HolochainError::ValidationFailed(
+                "stub_fn in zome test_zome takes params [x, y], found [r, theta]".to_string()
+            ))
+        );
+        let result: Result<Point, HolochainError> =
+            hc.call_json("test_zome", "stub_fn", &"not a point", None);
+        assert_eq!(
+            result,
+            Err(HolochainError::ValidationFailed(
+                "stub_fn in zome test_zome takes named params, found \"not a point\"".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn can_dispatch_actions() {
+        let (context, _) = mock_context(HCAgent::from_string("bob"));
+        let mut hc = Holochain::new(test_dna(), context).unwrap();
+        hc.register_function("test_zome", "stub_fn", |params| Ok(params.to_string()));
+        let call = fncall::Call::new("test_zome", "stub_fn").with_parameters("dispatched");
+        assert_eq!(
+            hc.dispatch_action(Nucleus(Call(call.clone()))),
+            Err(HolochainError::InstanceNotActive)
+        );
+
+        hc.start().expect("couldn't start");
+        hc.dispatch_action(Nucleus(Call(call.clone()))).unwrap();
+        // stopping consumes whatever the processing thread hasn't got to yet
+        hc.stop().unwrap();
+        assert_eq!(
+            hc.state().unwrap().nucleus().call_result(&call),
+            Some(Ok("dispatched".to_string()))
+        );
+    }
+
+    #[test]
+    fn can_replay_history() {
+        let (context, test_logger) = mock_context(HCAgent::from_string("bob"));
+        let mut hc = Holochain::new(test_dna(), context.clone()).unwrap();
+        hc.register_function("test_zome", "stub_fn", |params| Ok(params.to_string()));
+        hc.start().expect("couldn't start");
+        hc.commit_entry("person", "alice").unwrap();
+        hc.call("test_zome", "stub_fn", "hello", None).unwrap();
+        assert_eq!(
+            hc.replay(&hc.history()),
+            Err(HolochainError::InstanceActive)
+        );
+        hc.stop().unwrap();
+
+        // whatever the instance's state was, it is replaced
+        let mut replayed = Holochain::new(test_dna(), context).unwrap();
+        replayed.start().expect("couldn't start");
+        replayed.commit_entry("person", "bob").unwrap();
+        replayed.stop().unwrap();
+        let clone = replayed.clone();
+        replayed.replay(&hc.history()).unwrap();
+        assert_eq!(replayed.snapshot(), hc.snapshot());
+        assert_eq!(replayed.history(), hc.history());
+        assert_eq!(clone.snapshot(), hc.snapshot());
+        assert_eq!(replayed.status(), InstanceStatus::Stopped);
+        assert_eq!(
+            test_logger.lock().unwrap().dump().last(),
+            Some(&"[TestApp] replayed".to_string())
+        );
+    }
+
+    #[test]
+    fn can_get_agent_address() {
+        let agent = HCAgent::from_seed(b"some seed");
+        let (context, _) = mock_context(agent.clone());
+        let hc = Holochain::new(test_dna(), context).unwrap();
+        // the seeded agent is identified by its public key, which the address is the hash of
+        let public_key = agent.public_key().unwrap().to_base58();
+        assert_eq!(hc.agent_address(), hc_dna::hash::address(&public_key));
+        assert_eq!(
+            hc.agent_address(),
+            HCAgent::from_seed(b"some seed").address()
+        );
+        assert_ne!(
+            hc.agent_address(),
+            HCAgent::from_seed(b"other seed").address()
+        );
+    }
+
+    #[test]
+    fn calls_are_counted_in_metrics() {
+        let (context, _) = mock_context(HCAgent::from_string("bob"));
+        let mut hc = Holochain::new(test_dna(), context).unwrap();
+        hc.register_function("test_zome", "stub_fn", |params| Ok(params.to_string()));
+        hc.register_function("test_zome", "fail_fn", |_| {
+            Err(HolochainError::new("borked"))
+        });
+        assert_eq!(hc.metrics(), Metrics::new());
+
+        hc.start().expect("couldn't start");
+        hc.call("test_zome", "stub_fn", "", None).unwrap();
+        assert!(hc.call("test_zome", "fail_fn", "", None).is_err());
+        // calls rejected before being dispatched aren't served
+        assert!(hc.call("bogozome", "bogusfn", "", None).is_err());
+
+        let metrics = hc.metrics();
+        assert_eq!(metrics.calls_total, 2);
+        assert_eq!(metrics.calls_failed, 1);
+        assert!(metrics.last_call_duration.is_some());
+    }
+
+    #[test]
+    fn failing_call_reports_zome_and_function() {
+        let dna = test_dna();
+        let agent = HCAgent::from_string("bob");
+        let (context, _) = mock_context(agent.clone());
+        let mut hc = Holochain::new(dna.clone(), context).unwrap();
+        hc.register_function("test_zome", "fail_fn", |_| {
+            Err(HolochainError::new("borked"))
+        });
+        hc.start().expect("couldn't start");
+
+        let result = hc.call("test_zome", "fail_fn", "", None);
+        match result {
+            Err(HolochainError::ZomeFunctionFailed {
+                zome,
+                function,
+                reason,
+            }) => {
+                assert_eq!(zome, "test_zome");
+                assert_eq!(function, "fail_fn");
+                assert_eq!(reason, "borked");
+            }
+            _ => assert!(false),
+        };
+    }
+
+    #[test]
+    fn call_to_unknown_zome_fails() {
+        let dna = test_dna();
+        let agent = HCAgent::from_string("bob");
+        let (context, _) = mock_context(agent.clone());
+        let mut hc = Holochain::new(dna.clone(), context).unwrap();
+        hc.start().expect("couldn't start");
+
+        let result = hc.call("bogozome", "bogusfn", "", None);
+        assert_eq!(
+            result,
+            Err(HolochainError::ZomeNotFound("bogozome".to_string()))
+        );
+    }
+
+    #[test]
+    fn call_to_undeclared_function_fails() {
+        let dna = test_dna();
+        let agent = HCAgent::from_string("bob");
+        let (context, _) = mock_context(agent.clone());
+        let mut hc = Holochain::new(dna.clone(), context).unwrap();
+        // registering a function doesn't make up for the dna not declaring it
+        hc.register_function("test_zome", "undeclared_fn", |_| Ok("result".to_string()));
+        hc.start().expect("couldn't start");
+
+        let result = hc.call("test_zome", "undeclared_fn", "", None);
+        assert_eq!(
+            result,
+            Err(HolochainError::ZomeFunctionNotFound {
+                zome: "test_zome".to_string(),
+                function: "undeclared_fn".to_string(),
+            })
+        );
+        // nothing but the init was ever dispatched
+        assert_eq!(hc.history().len(), 1);
+    }
+
+    #[test]
+    fn calls_need_a_granted_capability() {
+        let dna = test_dna();
+        let agent = HCAgent::from_string("bob");
+        let (context, _) = mock_context(agent.clone());
+        let mut hc = Holochain::new(dna.clone(), context).unwrap();
+        hc.register_function("test_zome", "admin_fn", |_| Ok("admin result".to_string()));
+        hc.start().expect("couldn't start");
+        let denied = Err(HolochainError::CapabilityDenied {
+            zome: "test_zome".to_string(),
+            function: "admin_fn".to_string(),
+        });
+
+        let token = hc.grant_capability(vec!["admin_fn".to_string()]).unwrap();
+        let result = hc.call("test_zome", "admin_fn", "", Some(&token));
+        assert_eq!(result, Ok("admin result".to_string()));
+
+        assert_eq!(hc.call("test_zome", "admin_fn", "", None), denied);
+        assert_eq!(hc.call("test_zome", "admin_fn", "", Some("bogus")), denied);
+        // a token only grants the functions it was granted for
+        let other_token = hc.grant_capability(vec!["other_fn".to_string()]).unwrap();
+        assert_eq!(
+            hc.call("test_zome", "admin_fn", "", Some(&other_token)),
+            denied
+        );
+
+        assert_eq!(
+            hc.capability_grant(&token),
+            Some(CapabilityGrant::new(vec!["admin_fn".to_string()]))
+        );
+        assert_eq!(hc.capability_grant("bogus"), None);
+    }
+
+    #[test]
+    fn public_functions_need_no_capability() {
+        let dna = test_dna();
+        let agent = HCAgent::from_string("bob");
+        let (context, _) = mock_context(agent.clone());
+        let mut hc = Holochain::new(dna.clone(), context).unwrap();
+        hc.register_function("test_zome", "stub_fn", |_| Ok("stubbed result".to_string()));
+        hc.start().expect("couldn't start");
+
+        let result = hc.call("test_zome", "stub_fn", "", None);
+        assert_eq!(result, Ok("stubbed result".to_string()));
+        // presenting a token anyway does no harm
+        let result = hc.call("test_zome", "stub_fn", "", Some("bogus"));
+        assert_eq!(result, Ok("stubbed result".to_string()));
+    }
+
+    #[test]
+    fn can_get_history() {
+        let dna = test_dna();
+        let agent = HCAgent::from_string("bob");
+        let (context, _) = mock_context(agent.clone());
+        let mut hc = Holochain::new(dna.clone(), context).unwrap();
+        hc.register_function("test_zome", "stub_fn", |_| Ok("stubbed result".to_string()));
+        hc.start().expect("couldn't start");
+        hc.call("test_zome", "stub_fn", "", None).unwrap();
+        hc.call("test_zome", "stub_fn", "", None).unwrap();
+
+        // the init, then a call, its result and the caller taking it for each function call
+        let history = hc.history();
+        assert_eq!(history.len(), 7);
+        match history[0] {
+            Nucleus(InitApplication(_)) => assert!(true),
+            _ => assert!(false),
+        };
+        for actions in history[1..].chunks(3) {
+            match (&actions[0], &actions[1], &actions[2]) {
+                (
+                    Nucleus(Call(call)),
+                    Nucleus(ReturnResult(_, Ok(result))),
+                    Nucleus(TakeResult(_)),
+                ) => {
+                    assert_eq!(result, "stubbed result");
+                    // so the result isn't left in the state
+                    assert_eq!(hc.snapshot().nucleus().call_result(call), None);
+                }
+                _ => assert!(false),
+            };
+        }
+    }
+
+    #[test]
+    fn can_observe_state() {
+        let dna = test_dna();
+        let agent = HCAgent::from_string("bob");
+        let (context, _) = mock_context(agent.clone());
+        let mut hc = Holochain::new(dna.clone(), context).unwrap();
+        hc.register_function("test_zome", "stub_fn", |_| Ok("stubbed result".to_string()));
+        hc.start().expect("couldn't start");
+
+        let receiver = hc.observe();
+        hc.call("test_zome", "stub_fn", "", None).unwrap();
+        let state = receiver.try_recv().unwrap();
+        assert_eq!(state, hc.state().unwrap());
+    }
+
+    #[test]
+    fn can_commit_entry() {
+        let dna = test_dna();
+        let agent = HCAgent::from_string("bob");
+        let (context, _) = mock_context(agent.clone());
+        let mut hc = Holochain::new(dna.clone(), context).unwrap();
+        let result = hc.commit_entry("test_type", "some content");
+        assert_eq!(result, Err(HolochainError::InstanceNotActive));
+
+        hc.start().expect("couldn't start");
+        let address = hc.commit_entry("test_type", "some content").unwrap();
+        assert!(!address.is_empty());
+        assert!(hc.snapshot().agent().has_entry(&address));
+        assert_eq!(
+            hc.get_entry(&address),
+            Ok(Some(Entry::new("test_type", "some content")))
+        );
+    }
+
+    #[test]
+    fn signed_commits_can_be_verified() {
+        let dna = test_dna();
+        let agent = HCAgent::from_seed(b"bob");
+        let (context, _) = mock_context(agent.clone());
+        let mut hc = Holochain::new(dna.clone(), context).unwrap();
+        hc.start().expect("couldn't start");
+        let address = hc.commit_entry("test_type", "some content").unwrap();
+        assert_eq!(hc.verify_entry(&address), Ok(true));
+        assert_eq!(hc.verify_entry("bogus address"), Ok(false));
+
+        let provenance = hc.snapshot().agent().provenance(&address).unwrap();
+        assert_eq!(provenance.author, agent.address());
+        assert_eq!(provenance.public_key, agent.public_key().unwrap());
+
+        // agents without keys can still commit, but not sign
+        let (context, _) = mock_context(HCAgent::from_string("jane"));
+        let mut hc = Holochain::new(dna, context).unwrap();
+        hc.start().expect("couldn't start");
+        let address = hc.commit_entry("test_type", "some content").unwrap();
+        assert_eq!(hc.verify_entry(&address), Ok(false));
+    }
+
+    #[test]
+    fn commits_must_pass_validation() {
+        let dna = test_dna();
+        let agent = HCAgent::from_string("bob");
+        let (context, _) = mock_context(agent.clone());
+        let mut hc = Holochain::new(dna.clone(), context).unwrap();
+        hc.register_validator("valid_type", |_| Ok(()));
+        hc.register_validator("invalid_type", |_| Err("never valid".to_string()));
+        hc.start().expect("couldn't start");
+
+        let address = hc.commit_entry("valid_type", "some content").unwrap();
+        assert!(hc.get_entry(&address).unwrap().is_some());
+
+        let result = hc.commit_entry("invalid_type", "some content");
+        assert_eq!(
+            result,
+            Err(HolochainError::ValidationFailed("never valid".to_string()))
+        );
+        let address = Entry::new("invalid_type", "some content").address();
+        assert_eq!(hc.get_entry(&address), Ok(None));
+
+        let address = hc.commit_entry("other_type", "some content").unwrap();
+        assert!(hc.get_entry(&address).unwrap().is_some());
+    }
+
+    #[test]
+    fn can_get_committed_entry() {
+        let dna = test_dna();
+        let agent = HCAgent::from_string("bob");
+        let (context, _) = mock_context(agent.clone());
+        let mut hc = Holochain::new(dna.clone(), context).unwrap();
+        hc.start().expect("couldn't start");
+        let address = hc.commit_entry("test_type", "some content").unwrap();
+        hc.stop().expect("couldn't stop");
+
+        // reading doesn't need the instance to be active
+        let entry = hc.get_entry(&address).unwrap().unwrap();
+        assert_eq!(entry.entry_type(), "test_type");
+        assert_eq!(entry.content(), "some content");
+    }
+
+    #[test]
+    fn can_link_entries() {
+        let dna = test_dna();
+        let agent = HCAgent::from_string("bob");
+        let (context, _) = mock_context(agent.clone());
+        let mut hc = Holochain::new(dna.clone(), context).unwrap();
+        hc.start().expect("couldn't start");
+        let base = hc.commit_entry("person", "alice").unwrap();
+        let target = hc.commit_entry("person", "bob").unwrap();
+        assert!(hc.get_links(&base, "follows").is_empty());
+
+        hc.link_entries(&base, &target, "follows").unwrap();
+        assert_eq!(hc.get_links(&base, "follows"), vec![target.clone()]);
+        assert!(hc.get_links(&base, "likes").is_empty());
+        assert!(hc.get_links(&target, "follows").is_empty());
+    }
+
+    #[test]
+    fn entries_are_fetched_from_the_dht_when_not_held_locally() {
+        use hc_core::network::dht::MemoryDht;
+
+        // both nodes share the same in-memory dht
+        let dht = MemoryDht::new();
+        let node = |name: &str| {
+            let context = Arc::new(Context {
+                dht: Some(Arc::new(Mutex::new(dht.clone()))),
+                ..(*mock_context(HCAgent::from_string(name)).0).clone()
+            });
+            let mut hc = Holochain::new(test_dna(), context).unwrap();
+            hc.start().expect("couldn't start");
+            hc
+        };
+        let mut alice = node("alice");
+        let bob = node("bob");
+
+        let address = alice.commit_entry("post", "hello").unwrap();
+        assert!(!bob.snapshot().agent().has_entry(&address));
+        assert_eq!(
+            bob.get_entry(&address),
+            Ok(Some(Entry::new("post", "hello")))
+        );
+        assert_eq!(bob.get_entry("QmBogusAddress"), Ok(None));
+
+        // removing an entry locally doesn't bring it back from the dht
+        alice.remove_entry(&address).unwrap();
+        assert_eq!(alice.get_entry(&address), Ok(None));
+    }
+
+    #[test]
+    fn commits_go_through_the_entry_store() {
+        use hc_core::entry_store::{EntryStore, FileEntryStore};
+
+        let path =
+            std::env::temp_dir().join(format!("hc_core_api_entries_{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let context = Arc::new(Context {
+            entry_store: Arc::new(Mutex::new(FileEntryStore::new(&path))),
+            ..(*mock_context(HCAgent::from_string("bob")).0).clone()
+        });
+        let mut hc = Holochain::new(test_dna(), context).unwrap();
+        hc.register_validator("post", |entry| {
+            if entry.content().is_empty() {
+                Err("posts can't be empty".to_string())
+            } else {
+                Ok(())
+            }
+        });
+        hc.start().expect("couldn't start");
+        let address = hc.commit_entry("post", "hello").unwrap();
+        assert!(hc.commit_entry("post", "").is_err());
+
+        // a store on the same file sees only the valid entry
+        let store = FileEntryStore::new(&path);
+        let entry = Entry::new("post", "hello");
+        assert_eq!(store.get(&address), Ok(Some(entry.clone())));
+        assert_eq!(store.all_of_type("post"), Ok(vec![entry.clone()]));
+        assert_eq!(hc.get_entry(&address), Ok(Some(entry.clone())));
+        assert_eq!(hc.query("post"), Ok(vec![entry]));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn dna_seed_entries_are_committed_on_init() {
+        let mut dna = test_dna();
+        dna.seed_entries = vec![
+            SeedEntry::new("post", "hello"),
+            SeedEntry::new("post", "goodbye"),
+        ];
+        let (context, _) = mock_context(HCAgent::from_string("bob"));
+        let hc = Holochain::new(dna.clone(), context.clone()).unwrap();
+        assert_eq!(
+            hc.query("post"),
+            Ok(vec![
+                Entry::new("post", "hello"),
+                Entry::new("post", "goodbye")
+            ])
+        );
+
+        // seed entries must pass validation
+        let mut entry_type = hc_dna::zome::entry_types::EntryType::new();
+        entry_type.name = "post".to_string();
+        entry_type.schema = Some(r#"{"type": "object"}"#.to_string());
+        dna.zomes[0].entry_types.push(entry_type);
+        match Holochain::new(dna, context) {
+            Err(HolochainError::ValidationFailed(_)) => {}
+            _ => assert!(false),
+        };
+    }
+
+    #[test]
+    fn read_only_view_sees_commits() {
+        let (context, _) = mock_context(HCAgent::from_string("bob"));
+        let mut hc = Holochain::new(test_dna(), context).unwrap();
+        let view = hc.read_only();
+        assert_eq!(view.name(), Some("TestApp".to_string()));
+        assert_eq!(view.query("post"), Ok(Vec::new()));
+
+        hc.start().expect("couldn't start");
+        let address = hc.commit_entry("post", "hello").unwrap();
+        let entry = Entry::new("post", "hello");
+        assert_eq!(view.get_entry(&address), Ok(Some(entry.clone())));
+        assert_eq!(view.query("post"), Ok(vec![entry]));
+        assert_eq!(view.snapshot(), hc.snapshot());
+        assert_eq!(view.state(), Ok(hc.snapshot()));
+    }
+
+    #[test]
+    fn can_update_entries() {
+        let (context, _) = mock_context(HCAgent::from_string("bob"));
+        let mut hc = Holochain::new(test_dna(), context).unwrap();
+        hc.start().expect("couldn't start");
+        let old = hc.commit_entry("post", "helo").unwrap();
+
+        let new = hc.update_entry(&old, "hello").unwrap();
+        assert_eq!(new, Entry::new("post", "hello").address());
+        assert_eq!(hc.get_entry(&old), Ok(Some(Entry::new("post", "helo"))));
+        assert_eq!(hc.get_entry(&new), Ok(Some(Entry::new("post", "hello"))));
+        assert_eq!(hc.get_updated_address(&old), Some(new.clone()));
+        assert_eq!(hc.get_updated_address(&new), None);
+
+        assert_eq!(
+            hc.update_entry("bogus", "hello"),
+            Err(HolochainError::EntryNotFound("bogus".to_string()))
+        );
+    }
+
+    #[test]
+    fn commits_are_recorded_on_the_source_chain() {
+        let agent = HCAgent::from_seed(b"bob");
+        let (context, _) = mock_context(agent.clone());
+        let mut hc = Holochain::new(test_dna(), context).unwrap();
+        hc.start().expect("couldn't start");
+        assert!(hc.chain().is_empty());
+
+        let first = hc.commit_entry("post", "first").unwrap();
+        let second = hc.commit_entry("comment", "second").unwrap();
+        let third = hc.commit_entry("post", "third").unwrap();
+
+        let chain = hc.chain();
+        assert_eq!(chain.len(), 3);
+        let addresses: Vec<String> = chain.iter().map(|pair| pair.entry().address()).collect();
+        assert_eq!(addresses, vec![first, second, third]);
+        assert_eq!(chain[0].header().previous(), None);
+        assert_eq!(chain[1].header().previous(), Some(chain[0].header().hash()));
+        assert_eq!(chain[2].header().previous(), Some(chain[1].header().hash()));
+        assert_eq!(chain[1].header().author(), Some(agent.address()));
+        assert_eq!(chain[1].header().entry_type(), "comment");
+        assert!(chain[1].header().timestamp().is_some());
+
+        // the chain is rebuilt the same from the history
+        hc.stop().unwrap();
+        let (context, _) = mock_context(agent);
+        let mut replayed = Holochain::new(test_dna(), context).unwrap();
+        replayed.replay(&hc.history()).unwrap();
+        assert_eq!(replayed.chain(), chain);
+    }
+
+    #[test]
+    fn removed_entries_are_not_returned() {
+        let (context, _) = mock_context(HCAgent::from_string("bob"));
+        let mut hc = Holochain::new(test_dna(), context).unwrap();
+        hc.start().expect("couldn't start");
+        let address = hc.commit_entry("post", "hello").unwrap();
+
+        hc.remove_entry(&address).unwrap();
+        assert_eq!(hc.get_entry(&address), Ok(None));
+        assert_eq!(hc.query("post"), Ok(Vec::new()));
+        assert_eq!(
+            hc.remove_entry(&address),
+            Err(HolochainError::EntryNotFound(address.clone()))
+        );
+        assert_eq!(
+            hc.update_entry(&address, "goodbye"),
+            Err(HolochainError::EntryNotFound(address.clone()))
+        );
+
+        // committing it again brings it back
+        assert_eq!(hc.commit_entry("post", "hello"), Ok(address.clone()));
+        assert!(hc.get_entry(&address).unwrap().is_some());
+        assert_eq!(hc.query("post").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn can_query_entries_by_type() {
+        let (context, _) = mock_context(HCAgent::from_string("bob"));
+        let mut hc = Holochain::new(test_dna(), context).unwrap();
+        hc.start().expect("couldn't start");
+        assert_eq!(hc.query("person"), Ok(Vec::new()));
+
+        for &(entry_type, content) in &[
+            ("person", "zoe"),
+            ("post", "hello"),
+            ("person", "alice"),
+            ("post", "goodbye"),
+            ("person", "bob"),
+        ] {
+            hc.commit_entry(entry_type, content).unwrap();
+        }
+        assert_eq!(
+            hc.query("person"),
+            Ok(vec![
+                Entry::new("person", "zoe"),
+                Entry::new("person", "alice"),
+                Entry::new("person", "bob"),
+            ])
+        );
+        assert_eq!(
+            hc.query("post"),
+            Ok(vec![
+                Entry::new("post", "hello"),
+                Entry::new("post", "goodbye")
+            ])
+        );
+        assert_eq!(hc.query("bogus"), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn linking_missing_entries_fails() {
+        let dna = test_dna();
+        let agent = HCAgent::from_string("bob");
+        let (context, _) = mock_context(agent.clone());
+        let mut hc = Holochain::new(dna.clone(), context).unwrap();
+        let target = Entry::new("person", "bob").address();
+        assert_eq!(
+            hc.link_entries("Qmbogus", &target, "follows"),
+            Err(HolochainError::InstanceNotActive)
+        );
+
+        hc.start().expect("couldn't start");
+        assert_eq!(
+            hc.link_entries("Qmbogus", &target, "follows"),
+            Err(HolochainError::EntryNotFound("Qmbogus".to_string()))
+        );
+        let base = hc.commit_entry("person", "alice").unwrap();
+        assert_eq!(
+            hc.link_entries(&base, &target, "follows"),
+            Err(HolochainError::EntryNotFound(target.clone()))
+        );
+        assert!(hc.get_links(&base, "follows").is_empty());
+    }
+
+    #[test]
+    fn get_missing_entry_returns_none() {
+        let dna = test_dna();
+        let agent = HCAgent::from_string("bob");
+        let (context, _) = mock_context(agent.clone());
+        let hc = Holochain::new(dna.clone(), context).unwrap();
+        assert_eq!(hc.get_entry("QmBogusAddress"), Ok(None));
+    }
+
+    #[test]
+    fn can_get_state() {
+        let dna = test_dna();
+        let agent = HCAgent::from_string("bob");
+        let (context, _) = mock_context(agent.clone());
+        let hc = Holochain::new(dna.clone(), context).unwrap();
+
+        let result = hc.state();
+        match result {
+            Ok(state) => {
+                assert_eq!(state.nucleus().dna(), Some(dna));
+            }
+            Err(_) => assert!(false),
+        };
+    }
+
+    #[test]
+    fn snapshot_is_unaffected_by_later_actions() {
+        let dna = test_dna();
+        let agent = HCAgent::from_string("bob");
+        let (context, _) = mock_context(agent.clone());
+        let mut hc = Holochain::new(dna.clone(), context).unwrap();
+        hc.start().expect("couldn't start");
+
+        let snapshot = hc.snapshot();
+        assert_eq!(snapshot, hc.state().unwrap());
+
+        let address = hc.commit_entry("post", "hello").unwrap();
+        assert!(hc.snapshot().agent().has_entry(&address));
+        assert!(!snapshot.agent().has_entry(&address));
+        assert_ne!(snapshot, hc.state().unwrap());
+    }
+
+    #[test]
+    fn can_diff_snapshots() {
+        let dna = test_dna();
+        let agent = HCAgent::from_string("bob");
+        let (context, _) = mock_context(agent.clone());
+        let mut hc = Holochain::new(dna.clone(), context).unwrap();
+        hc.start().expect("couldn't start");
+
+        let before = hc.snapshot();
+        assert!(before.diff(&hc.snapshot()).is_empty());
+
+        hc.commit_entry("test_type", "some content").unwrap();
+        let after = hc.snapshot();
+
+        let address = Entry::new("test_type", "some content").address();
+        let diff = before.diff(&after);
+        assert_eq!(diff.added_entries, vec![address.clone()]);
+        assert!(diff.removed_entries.is_empty());
+        assert!(diff.nucleus_changes.is_empty());
+
+        // diffing the other way round sees the entry removed
+        assert_eq!(after.diff(&before).removed_entries, vec![address.clone()]);
+
+        // as does removing it
+        hc.remove_entry(&address).unwrap();
+        let removed = after.diff(&hc.snapshot());
+        assert_eq!(removed.removed_entries, vec![address]);
+        assert!(removed.added_entries.is_empty());
+        assert!(before.diff(&hc.snapshot()).is_empty());
     }
 }
@@ -0,0 +1,66 @@
+/*!
+hc_dna::error holds the errors that can occur while working with dna.
+*/
+
+use std::error::Error;
+use std::fmt;
+
+/// Represents errors that can occur while working with dna.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DnaError {
+    /// The dna could not be read, e.g. a missing package file.
+    Io(String),
+    /// The dna could not be parsed from json.
+    Parse(String),
+    /// The dna is structurally invalid, e.g. it has no name.
+    Invalid(String),
+    /// The dna could not be serialized to json.
+    Serialize(String),
+}
+
+impl fmt::Display for DnaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DnaError::Io(msg) => write!(f, "could not read dna: {}", msg),
+            DnaError::Parse(msg) => write!(f, "could not parse dna: {}", msg),
+            DnaError::Invalid(msg) => write!(f, "invalid dna: {}", msg),
+            DnaError::Serialize(msg) => write!(f, "could not serialize dna: {}", msg),
+        }
+    }
+}
+
+impl Error for DnaError {
+    fn description(&self) -> &str {
+        match self {
+            DnaError::Io(_) => "could not read dna",
+            DnaError::Parse(_) => "could not parse dna",
+            DnaError::Invalid(_) => "invalid dna",
+            DnaError::Serialize(_) => "could not serialize dna",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_display() {
+        assert_eq!(
+            format!("{}", DnaError::Io("missing.hcpkg".to_string())),
+            "could not read dna: missing.hcpkg"
+        );
+        assert_eq!(
+            format!("{}", DnaError::Parse("bad json".to_string())),
+            "could not parse dna: bad json"
+        );
+        assert_eq!(
+            format!("{}", DnaError::Invalid("no name".to_string())),
+            "invalid dna: no name"
+        );
+        assert_eq!(
+            format!("{}", DnaError::Serialize("bad key".to_string())),
+            "could not serialize dna: bad key"
+        );
+    }
+}
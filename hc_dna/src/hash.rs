@@ -0,0 +1,44 @@
+/*!
+hc_dna::hash holds the content addressing shared by dna, entries and agents,
+so that identical content always has the same address.
+*/
+
+use multihash;
+use rust_base58::ToBase58;
+
+/**
+The address of some content, which is the base58 encoded sha2-256 multihash of it.
+
+# Examples
+
+```
+use hc_dna::hash::address;
+
+assert_eq!(address("hello"), address("hello"));
+assert!(address("hello").starts_with("Qm"));
+```
+*/
+pub fn address(content: &str) -> String {
+    // sha2-256 is always supported, so encoding can't fail
+    multihash::encode(multihash::Hash::SHA2256, content.as_bytes())
+        .unwrap()
+        .to_base58()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn address_is_deterministic() {
+        assert_eq!(address("some content"), address("some content"));
+        assert_eq!(address(""), address(""));
+    }
+
+    #[test]
+    fn different_content_has_different_addresses() {
+        assert_ne!(address("some content"), address("other content"));
+        assert_ne!(address("some content"), address("some content "));
+        assert_ne!(address(""), address(" "));
+    }
+}
@@ -28,10 +28,21 @@ extern crate serde_derive;
 extern crate serde;
 #[macro_use]
 extern crate serde_json;
+extern crate multihash;
+extern crate rust_base58;
 extern crate uuid;
 
+use error::DnaError;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
 use uuid::Uuid;
 
+pub mod error;
+
+pub mod hash;
+
 pub mod wasm;
 
 pub mod zome;
@@ -46,8 +57,29 @@ fn _def_new_uuid() -> String {
     Uuid::new_v4().to_string()
 }
 
+/// hash helper, recursively sorts object keys so equivalent json values serialize identically
+fn _canonical_json(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(map) => {
+            let mut entries: Vec<(String, serde_json::Value)> = map.into_iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            serde_json::Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key, _canonical_json(value)))
+                    .collect(),
+            )
+        }
+        serde_json::Value::Array(values) => {
+            serde_json::Value::Array(values.into_iter().map(_canonical_json).collect())
+        }
+        other => other,
+    }
+}
+
 /// Represents the top-level holochain dna object.
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
 pub struct Dna {
     /// The top-level "name" of a holochain application.
     #[serde(default)]
@@ -76,6 +108,37 @@ pub struct Dna {
     /// An array of zomes associated with your holochain application.
     #[serde(default)]
     pub zomes: Vec<zome::Zome>,
+
+    /// Entries committed when an instance is initialized, e.g. for demos and tests.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub seed_entries: Vec<SeedEntry>,
+}
+
+/// Represents an entry in the top-level "seed_entries" array.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SeedEntry {
+    /// The name of the entry type this entry is of.
+    pub entry_type: String,
+
+    /// The content of the entry.
+    pub content: String,
+}
+
+impl SeedEntry {
+    pub fn new(entry_type: &str, content: &str) -> Self {
+        SeedEntry {
+            entry_type: String::from(entry_type),
+            content: String::from(content),
+        }
+    }
+}
+
+impl PartialEq for Dna {
+    /// Dnas are equal if they hash the same, so insignificant differences like the order
+    /// zomes were added in or properties were set in don't count.
+    fn eq(&self, other: &Dna) -> bool {
+        self.hash() == other.hash()
+    }
 }
 
 impl Default for Dna {
@@ -89,6 +152,7 @@ impl Default for Dna {
             dna_spec_version: String::from("2.0"),
             properties: _def_empty_object(),
             zomes: Vec::new(),
+            seed_entries: Vec::new(),
         }
     }
 }
@@ -130,6 +194,25 @@ impl Dna {
         serde_json::from_str(dna)
     }
 
+    /**
+    Create a new in-memory dna struct from a json string,
+    failing with a DnaError on malformed json or unknown fields.
+
+    # Examples
+
+    ```
+    use hc_dna::Dna;
+
+    let dna = Dna::from_json(r#"{"name": "MyTestApp"}"#).unwrap();
+    assert_eq!("MyTestApp", dna.name);
+
+    assert!(Dna::from_json(r#"{"bogus": 1}"#).is_err());
+    ```
+    */
+    pub fn from_json(dna: &str) -> Result<Self, DnaError> {
+        serde_json::from_str(dna).map_err(|e| DnaError::Parse(e.to_string()))
+    }
+
     /**
     Generate a json string from an in-memory dna struct.
 
@@ -143,8 +226,8 @@ impl Dna {
 
     ```
     */
-    pub fn to_json(&self) -> serde_json::Result<String> {
-        serde_json::to_string(self)
+    pub fn to_json(&self) -> Result<String, DnaError> {
+        serde_json::to_string(self).map_err(|e| DnaError::Serialize(e.to_string()))
     }
 
     /**
@@ -163,6 +246,197 @@ impl Dna {
     pub fn to_json_pretty(&self) -> serde_json::Result<String> {
         serde_json::to_string_pretty(self)
     }
+
+    /**
+    Generate a content-addressable hash of an in-memory dna struct.
+    This is the base58 encoded sha2-256 multihash of the dna's canonical json,
+    so dnas with the same content hash identically regardless of key ordering
+    or of the order their zomes were added in.
+
+    # Examples
+
+    ```
+    use hc_dna::Dna;
+
+    let dna = Dna::new();
+    assert_eq!(dna.hash(), dna.clone().hash());
+    assert!(dna.hash().starts_with("Qm"));
+    ```
+    */
+    pub fn hash(&self) -> String {
+        // serializing a dna to a json value and hashing with sha2-256 can't fail
+        let mut value = serde_json::to_value(self).unwrap();
+        // zome names are unique, so sorting by them puts zomes in a canonical order
+        if let Some(zomes) = value["zomes"].as_array_mut() {
+            zomes.sort_by(|a, b| a["name"].as_str().cmp(&b["name"].as_str()));
+        }
+        hash::address(&_canonical_json(value).to_string())
+    }
+
+    /**
+    Check that an in-memory dna struct is structurally sound:
+    it must have a name, and no two zomes may share a name.
+
+    # Examples
+
+    ```
+    use hc_dna::Dna;
+
+    let mut dna = Dna::new();
+    assert!(dna.validate().is_err());
+
+    dna.name = String::from("MyTestApp");
+    assert!(dna.validate().is_ok());
+    ```
+    */
+    pub fn validate(&self) -> Result<(), DnaError> {
+        if self.name.is_empty() {
+            return Err(DnaError::Invalid(String::from("name is empty")));
+        }
+        let mut zome_names = HashSet::new();
+        for zome in &self.zomes {
+            if !zome_names.insert(&zome.name) {
+                return Err(DnaError::Invalid(format!(
+                    "duplicate zome name: {}",
+                    zome.name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /**
+    Look up a zome of this dna by its name.
+
+    # Examples
+
+    ```
+    use hc_dna::Dna;
+    use hc_dna::zome::Zome;
+
+    let mut dna = Dna::new();
+    let mut zome = Zome::new();
+    zome.name = String::from("my_zome");
+    dna.zomes.push(zome);
+
+    assert!(dna.get_zome("my_zome").is_some());
+    assert!(dna.get_zome("other_zome").is_none());
+    ```
+    */
+    pub fn get_zome(&self, zome_name: &str) -> Option<&zome::Zome> {
+        self.zomes.iter().find(|zome| zome.name == zome_name)
+    }
+
+    /// The signature declared for a function of a zome, if the zome has the function
+    /// and any of the capabilities declaring it gives a signature.
+    pub fn function_signature(
+        &self,
+        zome_name: &str,
+        fn_name: &str,
+    ) -> Option<&zome::capabilities::FunctionSignature> {
+        self.get_zome(zome_name)?
+            .capabilities
+            .iter()
+            .flat_map(|capability| capability.fn_declarations.iter())
+            .filter(|fn_declaration| fn_declaration.name == fn_name)
+            .filter_map(|fn_declaration| fn_declaration.signature.as_ref())
+            .next()
+    }
+
+    /// the names of all the zomes in this dna, in declaration order
+    pub fn zome_names(&self) -> Vec<String> {
+        self.zomes.iter().map(|zome| zome.name.clone()).collect()
+    }
+
+    /// all the zomes in this dna, in declaration order
+    pub fn zomes(&self) -> &[zome::Zome] {
+        &self.zomes
+    }
+
+    /**
+    Add a zome to this dna under the given name, which replaces any name the zome had.
+    Fails if the dna already has a zome with that name.
+
+    # Examples
+
+    ```
+    use hc_dna::Dna;
+    use hc_dna::zome::ZomeBuilder;
+
+    let mut dna = Dna::new();
+    let zome = ZomeBuilder::new()
+        .with_function("main", "my_fn")
+        .build();
+    dna.add_zome("my_zome", zome.clone()).unwrap();
+
+    assert!(dna.get_zome("my_zome").unwrap().has_function("my_fn"));
+    assert!(dna.add_zome("my_zome", zome).is_err());
+    ```
+    */
+    pub fn add_zome(&mut self, zome_name: &str, mut zome: zome::Zome) -> Result<(), DnaError> {
+        if self.get_zome(zome_name).is_some() {
+            return Err(DnaError::Invalid(format!(
+                "duplicate zome name: {}",
+                zome_name
+            )));
+        }
+        zome.name = zome_name.to_string();
+        self.zomes.push(zome);
+        Ok(())
+    }
+
+    /**
+    Look up an application property of this dna.
+    String properties are returned as they are, any other value as its json.
+
+    # Examples
+
+    ```
+    use hc_dna::Dna;
+
+    let mut dna = Dna::new();
+    assert_eq!(None, dna.get_property("network"));
+
+    dna.set_property("network", "testnet");
+    assert_eq!(Some(String::from("testnet")), dna.get_property("network"));
+    ```
+    */
+    pub fn get_property(&self, key: &str) -> Option<String> {
+        match self.properties.get(key) {
+            Some(serde_json::Value::String(value)) => Some(value.clone()),
+            Some(value) => Some(value.to_string()),
+            None => None,
+        }
+    }
+
+    /// Set an application property of this dna, which changes its hash.
+    pub fn set_property(&mut self, key: &str, value: &str) {
+        if !self.properties.is_object() {
+            self.properties = _def_empty_object();
+        }
+        self.properties[key] = serde_json::Value::String(value.to_string());
+    }
+}
+
+/**
+Load an in-memory dna struct from a json-encoded package file on disk.
+
+# Examples
+
+```
+use hc_dna::from_package_file;
+use std::path::Path;
+
+let dna = from_package_file(Path::new("test-fixtures/test.hcpkg")).unwrap();
+assert_eq!("test package", dna.name);
+```
+*/
+pub fn from_package_file(path: &Path) -> Result<Dna, DnaError> {
+    let mut json = String::new();
+    File::open(path)
+        .and_then(|mut file| file.read_to_string(&mut json))
+        .map_err(|e| DnaError::Io(format!("{}: {}", path.display(), e)))?;
+    Dna::new_from_json(&json).map_err(|e| DnaError::Parse(format!("{}: {}", path.display(), e)))
 }
 
 #[cfg(test)]
@@ -193,6 +467,255 @@ mod tests {
         assert_eq!(String::from("2.0"), deserialized.dna_spec_version);
     }
 
+    #[test]
+    fn json_round_trip_is_lossless() {
+        let mut populated = Dna::new();
+        populated.name = String::from("test");
+        populated.description = String::from("test");
+        populated.version = String::from("0.0.1");
+        populated.properties = json!({"str": "hello", "arr": [1, 2], "obj": {"a": 1}});
+        let mut zome = zome::Zome::new();
+        zome.name = String::from("test_zome");
+        let mut entry_type = zome::entry_types::EntryType::new();
+        entry_type.name = String::from("test_type");
+        entry_type.validation.code = vec![0, 1, 2, 3];
+        entry_type.links_to.push(zome::entry_types::LinksTo::new());
+        zome.entry_types.push(entry_type);
+        let mut capability = zome::capabilities::Capability::new();
+        capability
+            .fn_declarations
+            .push(zome::capabilities::FnDeclaration::new());
+        zome.capabilities.push(capability);
+        populated.zomes.push(zome);
+
+        for dna in &[Dna::new(), populated] {
+            assert_eq!(*dna, Dna::from_json(&dna.to_json().unwrap()).unwrap());
+            assert_eq!(
+                *dna,
+                Dna::new_from_json(&dna.to_json_pretty().unwrap()).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn parse_fails_on_garbage() {
+        assert!(Dna::new_from_json("").is_err());
+        assert!(Dna::new_from_json("not json").is_err());
+        assert!(Dna::new_from_json("[]").is_err());
+        assert!(Dna::new_from_json(r#"{"zomes": {}}"#).is_err());
+    }
+
+    #[test]
+    fn from_json_rejects_unknown_fields() {
+        match Dna::from_json(r#"{"bogus": 1}"#) {
+            Err(DnaError::Parse(msg)) => assert!(msg.contains("bogus")),
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+        assert!(Dna::from_json("not json").is_err());
+    }
+
+    #[test]
+    fn identical_dnas_hash_identically() {
+        let dna = Dna {
+            uuid: String::from(UNIT_UUID),
+            ..Default::default()
+        };
+        assert_eq!(dna.hash(), dna.clone().hash());
+
+        // key ordering doesn't matter
+        let dna1 = Dna::new_from_json(
+            r#"{
+                "uuid": "00000000-0000-0000-0000-000000000000",
+                "properties": {"a": 1, "b": {"c": 2, "d": 3}}
+            }"#,
+        ).unwrap();
+        let dna2 = Dna::new_from_json(
+            r#"{
+                "properties": {"b": {"d": 3, "c": 2}, "a": 1},
+                "uuid": "00000000-0000-0000-0000-000000000000"
+            }"#,
+        ).unwrap();
+        assert_ne!(dna1.to_json().unwrap(), dna2.to_json().unwrap());
+        assert_eq!(dna1.hash(), dna2.hash());
+    }
+
+    #[test]
+    fn hash_changes_with_content() {
+        let dna = Dna {
+            uuid: String::from(UNIT_UUID),
+            ..Default::default()
+        };
+        let hash = dna.hash();
+
+        let mut renamed = dna.clone();
+        renamed.name = String::from("renamed");
+        assert_ne!(hash, renamed.hash());
+
+        let mut versioned = dna.clone();
+        versioned.version = String::from("0.0.2");
+        assert_ne!(hash, versioned.hash());
+
+        let mut with_zome = dna.clone();
+        with_zome.zomes.push(zome::Zome::new());
+        assert_ne!(hash, with_zome.hash());
+
+        let mut changed_zome = with_zome.clone();
+        changed_zome.zomes[0].name = String::from("test_zome");
+        assert_ne!(with_zome.hash(), changed_zome.hash());
+    }
+
+    #[test]
+    fn can_set_and_get_properties() {
+        let mut dna = Dna::new_from_json(
+            r#"{
+                "properties": {"str": "hello", "num": 3}
+            }"#,
+        ).unwrap();
+        assert_eq!(dna.get_property("str"), Some(String::from("hello")));
+        assert_eq!(dna.get_property("num"), Some(String::from("3")));
+        assert_eq!(dna.get_property("bogus"), None);
+
+        let hash = dna.hash();
+        dna.set_property("str", "goodbye");
+        assert_eq!(dna.get_property("str"), Some(String::from("goodbye")));
+        assert_ne!(hash, dna.hash());
+
+        let mut forked = dna.clone();
+        forked.set_property("network", "testnet");
+        assert_ne!(dna.hash(), forked.hash());
+        let json = forked.to_json().unwrap();
+        assert_eq!(forked, Dna::new_from_json(&json).unwrap());
+
+        // properties that aren't an object are replaced when set
+        dna.properties = serde_json::Value::Null;
+        dna.set_property("network", "testnet");
+        assert_eq!(dna.get_property("network"), Some(String::from("testnet")));
+    }
+
+    #[test]
+    fn validate_requires_name() {
+        let mut dna = Dna::new();
+        assert_eq!(
+            dna.validate(),
+            Err(DnaError::Invalid(String::from("name is empty")))
+        );
+
+        dna.name = String::from("test");
+        assert_eq!(dna.validate(), Ok(()));
+    }
+
+    #[test]
+    fn validate_requires_unique_zome_names() {
+        let mut dna = Dna::new();
+        dna.name = String::from("test");
+        for name in &["zome1", "zome2"] {
+            let mut zome = zome::Zome::new();
+            zome.name = name.to_string();
+            dna.zomes.push(zome);
+        }
+        assert_eq!(dna.validate(), Ok(()));
+
+        let mut zome = zome::Zome::new();
+        zome.name = String::from("zome1");
+        dna.zomes.push(zome);
+        assert_eq!(
+            dna.validate(),
+            Err(DnaError::Invalid(String::from(
+                "duplicate zome name: zome1"
+            )))
+        );
+    }
+
+    #[test]
+    fn can_add_zomes() {
+        let mut dna = Dna::new();
+        dna.name = String::from("test");
+        dna.add_zome(
+            "zome1",
+            zome::ZomeBuilder::new().with_function("main", "fn_a").build(),
+        ).unwrap();
+        dna.add_zome("zome2", zome::Zome::new()).unwrap();
+
+        assert_eq!(
+            dna.zomes()
+                .iter()
+                .map(|zome| zome.name.clone())
+                .collect::<Vec<String>>(),
+            vec!["zome1", "zome2"]
+        );
+        assert!(dna.zomes()[0].has_function("fn_a"));
+        assert_eq!(dna.validate(), Ok(()));
+
+        assert_eq!(
+            dna.add_zome("zome1", zome::Zome::new()),
+            Err(DnaError::Invalid(String::from(
+                "duplicate zome name: zome1"
+            )))
+        );
+        assert_eq!(dna.zomes().len(), 2);
+    }
+
+    #[test]
+    fn seed_entries_are_optional() {
+        let dna = Dna::new_from_json(r#"{"name": "test"}"#).unwrap();
+        assert!(dna.seed_entries.is_empty());
+        assert!(!dna.to_json().unwrap().contains("seed_entries"));
+
+        let dna = Dna::new_from_json(
+            r#"{
+                "name": "test",
+                "seed_entries": [{"entry_type": "post", "content": "hello"}]
+            }"#,
+        ).unwrap();
+        assert_eq!(dna.seed_entries, vec![SeedEntry::new("post", "hello")]);
+    }
+
+    #[test]
+    fn zome_order_is_insignificant() {
+        let build = |zome_names: &[&str]| {
+            let mut dna = Dna::new();
+            dna.name = String::from("test");
+            dna.uuid = String::from(UNIT_UUID);
+            for zome_name in zome_names {
+                let zome = zome::ZomeBuilder::new()
+                    .with_function("main", &format!("{}_fn", zome_name))
+                    .build();
+                dna.add_zome(zome_name, zome).unwrap();
+            }
+            dna
+        };
+        let dna = build(&["zome1", "zome2", "zome3"]);
+        let reordered = build(&["zome3", "zome1", "zome2"]);
+
+        assert_ne!(dna.zome_names(), reordered.zome_names());
+        assert_eq!(dna.hash(), reordered.hash());
+        assert_eq!(dna, reordered);
+
+        let reloaded = Dna::new_from_json(&reordered.to_json().unwrap()).unwrap();
+        assert_eq!(dna, reloaded);
+        assert_ne!(dna, build(&["zome1", "zome2"]));
+    }
+
+    #[test]
+    fn can_get_function_signature() {
+        let mut zome = zome::ZomeBuilder::new()
+            .with_function("main", "fn_a")
+            .with_function("main", "fn_b")
+            .build();
+        let signature = zome::capabilities::FunctionSignature {
+            inputs: vec![(String::from("title"), String::from("string"))],
+            output: String::from("string"),
+        };
+        zome.capabilities[0].fn_declarations[1].signature = Some(signature.clone());
+        let mut dna = Dna::new();
+        dna.add_zome("zome1", zome).unwrap();
+
+        assert_eq!(dna.function_signature("zome1", "fn_b"), Some(&signature));
+        assert_eq!(dna.function_signature("zome1", "fn_a"), None);
+        assert_eq!(dna.function_signature("zome1", "bogus_fn"), None);
+        assert_eq!(dna.function_signature("bogus_zome", "fn_b"), None);
+    }
+
     #[test]
     fn parse_and_serialize_compare() {
         let fixture = String::from(
@@ -413,6 +936,38 @@ mod tests {
         ).unwrap();
     }
 
+    // path to a file in the test fixtures directory
+    fn fixture_path(name: &str) -> std::path::PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("test-fixtures")
+            .join(name)
+    }
+
+    #[test]
+    fn can_load_package_file() {
+        let dna = from_package_file(&fixture_path("test.hcpkg")).unwrap();
+
+        assert_eq!("test package", dna.name);
+        assert_eq!(UNIT_UUID, dna.uuid);
+        assert_eq!("test_zome", dna.zomes[0].name);
+    }
+
+    #[test]
+    fn load_package_file_fails_if_missing() {
+        match from_package_file(&fixture_path("missing.hcpkg")) {
+            Err(DnaError::Io(msg)) => assert!(msg.contains("missing.hcpkg")),
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn load_package_file_fails_if_malformed() {
+        match from_package_file(&fixture_path("malformed.hcpkg")) {
+            Err(DnaError::Parse(msg)) => assert!(msg.contains("malformed.hcpkg")),
+            _ => assert!(false),
+        }
+    }
+
     #[test]
     fn parse_accepts_arbitrary_dna_properties() {
         let dna = Dna::new_from_json(
@@ -56,7 +56,10 @@ pub struct FnDeclaration {
     /// The name of this fn declaration.
     #[serde(default)]
     pub name: String,
-    // TODO - signature
+
+    /// The types this fn takes and returns, if declared.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<FunctionSignature>,
 }
 
 impl Default for FnDeclaration {
@@ -64,10 +67,23 @@ impl Default for FnDeclaration {
     fn default() -> Self {
         FnDeclaration {
             name: String::from(""),
+            signature: None,
         }
     }
 }
 
+/// Represents the "signature" object on a "fn_declarations" object.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+pub struct FunctionSignature {
+    /// The name and type of each parameter, in order.
+    #[serde(default)]
+    pub inputs: Vec<(String, String)>,
+
+    /// The type of the result.
+    #[serde(default)]
+    pub output: String,
+}
+
 impl FnDeclaration {
     /// Allow sane defaults for `FnDecrlaration::new()`.
     pub fn new() -> Self {
@@ -146,4 +162,33 @@ mod tests {
 
         assert_eq!(fixture, cap);
     }
+
+    #[test]
+    fn can_declare_signature() {
+        let fixture: FnDeclaration = serde_json::from_str(
+            r#"{
+                "name": "test",
+                "signature": {
+                    "inputs": [["title", "string"], ["stars", "integer"]],
+                    "output": "string"
+                }
+            }"#,
+        ).unwrap();
+
+        assert_eq!(
+            fixture.signature,
+            Some(FunctionSignature {
+                inputs: vec![
+                    (String::from("title"), String::from("string")),
+                    (String::from("stars"), String::from("integer")),
+                ],
+                output: String::from("string"),
+            })
+        );
+
+        // fn declarations without a signature serialize as they did before signatures
+        let mut fn_dec = FnDeclaration::new();
+        fn_dec.name = String::from("test");
+        assert_eq!(serde_json::to_string(&fn_dec).unwrap(), r#"{"name":"test"}"#);
+    }
 }
@@ -80,6 +80,11 @@ pub struct EntryType {
     /// An array of entry_types associated with this zome.
     #[serde(default)]
     pub links_to: Vec<LinksTo>,
+
+    /// A JSON Schema that the content of entries of this type must conform to.
+    /// Left out of the json when there is none, so existing dna keeps its hash.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<String>,
 }
 
 impl Default for EntryType {
@@ -91,6 +96,7 @@ impl Default for EntryType {
             sharing: Sharing::Public,
             validation: DnaWasm::new(),
             links_to: Vec::new(),
+            schema: None,
         }
     }
 }
@@ -143,4 +149,14 @@ mod tests {
 
         assert_eq!(fixture, entry);
     }
+
+    #[test]
+    fn schema_is_optional() {
+        let mut entry = EntryType::new();
+        assert!(!serde_json::to_string(&entry).unwrap().contains("schema"));
+
+        entry.schema = Some(String::from(r#"{"type": "string"}"#));
+        let json = serde_json::to_string(&entry).unwrap();
+        assert_eq!(serde_json::from_str::<EntryType>(&json).unwrap(), entry);
+    }
 }
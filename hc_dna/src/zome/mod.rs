@@ -91,6 +91,126 @@ impl Zome {
     pub fn new() -> Self {
         Default::default()
     }
+
+    /// The names of all functions declared by this zome's capabilities.
+    pub fn function_names(&self) -> Vec<String> {
+        self.capabilities
+            .iter()
+            .flat_map(|capability| capability.fn_declarations.iter())
+            .map(|fn_declaration| fn_declaration.name.clone())
+            .collect()
+    }
+
+    /// Check whether any of this zome's capabilities declares a function with the given name.
+    pub fn has_function(&self, fn_name: &str) -> bool {
+        !self.capabilities_for(fn_name).is_empty()
+    }
+
+    /// The capabilities that declare a function with the given name.
+    pub fn capabilities_for(&self, fn_name: &str) -> Vec<&capabilities::Capability> {
+        self.capabilities
+            .iter()
+            .filter(|capability| {
+                capability
+                    .fn_declarations
+                    .iter()
+                    .any(|fn_declaration| fn_declaration.name == fn_name)
+            })
+            .collect()
+    }
+
+    /// Check whether a function can be called by anyone, which it can if any capability
+    /// declaring it has a public membrane.
+    pub fn is_public(&self, fn_name: &str) -> bool {
+        self.capabilities_for(fn_name)
+            .iter()
+            .any(|capability| capability.capability.membrane == capabilities::Membrane::Public)
+    }
+
+    /// The wasm code implementing a function, which is that of the first capability
+    /// declaring it that has any.
+    pub fn code_for(&self, fn_name: &str) -> Option<&::wasm::DnaWasm> {
+        self.capabilities_for(fn_name)
+            .into_iter()
+            .map(|capability| &capability.code)
+            .find(|code| !code.code.is_empty())
+    }
+}
+
+/**
+Fluently assemble a `Zome`, declaring its functions by the capability that exposes them.
+
+# Examples
+
+```
+use hc_dna::zome::ZomeBuilder;
+use hc_dna::zome::capabilities::Membrane;
+
+let zome = ZomeBuilder::new()
+    .with_description("my zome")
+    .with_capability("main", Membrane::Public)
+    .with_function("main", "fn_a")
+    .with_function("admin", "fn_b")
+    .build();
+
+assert_eq!(zome.function_names(), vec!["fn_a", "fn_b"]);
+assert!(zome.is_public("fn_a"));
+assert!(!zome.is_public("fn_b"));
+```
+*/
+#[derive(Clone, Debug, Default)]
+pub struct ZomeBuilder {
+    zome: Zome,
+}
+
+impl ZomeBuilder {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn with_description(mut self, description: &str) -> Self {
+        self.zome.description = description.to_string();
+        self
+    }
+
+    pub fn with_entry_type(mut self, entry_type: entry_types::EntryType) -> Self {
+        self.zome.entry_types.push(entry_type);
+        self
+    }
+
+    /// Declare a capability with the given membrane, or change the membrane of one
+    /// already declared.
+    pub fn with_capability(mut self, name: &str, membrane: capabilities::Membrane) -> Self {
+        self.capability(name).capability.membrane = membrane;
+        self
+    }
+
+    /// Declare a function in the named capability, which is declared with the default
+    /// membrane if it doesn't exist yet.
+    pub fn with_function(mut self, capability: &str, fn_name: &str) -> Self {
+        let mut fn_declaration = capabilities::FnDeclaration::new();
+        fn_declaration.name = fn_name.to_string();
+        self.capability(capability)
+            .fn_declarations
+            .push(fn_declaration);
+        self
+    }
+
+    pub fn build(self) -> Zome {
+        self.zome
+    }
+
+    fn capability(&mut self, name: &str) -> &mut capabilities::Capability {
+        match self.zome.capabilities.iter().position(|c| c.name == name) {
+            Some(index) => &mut self.zome.capabilities[index],
+            None => {
+                let mut capability = capabilities::Capability::new();
+                capability.name = name.to_string();
+                self.zome.capabilities.push(capability);
+                self.zome.capabilities.last_mut().unwrap()
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -118,4 +238,79 @@ mod tests {
 
         assert_eq!(fixture, zome);
     }
+
+    #[test]
+    fn can_list_functions() {
+        let mut zome = Zome::new();
+        assert!(zome.function_names().is_empty());
+
+        for names in &[vec!["fn_a", "fn_b"], vec!["fn_c"]] {
+            let mut capability = capabilities::Capability::new();
+            for name in names {
+                let mut fn_declaration = capabilities::FnDeclaration::new();
+                fn_declaration.name = name.to_string();
+                capability.fn_declarations.push(fn_declaration);
+            }
+            zome.capabilities.push(capability);
+        }
+
+        assert_eq!(zome.function_names(), vec!["fn_a", "fn_b", "fn_c"]);
+        assert!(zome.has_function("fn_c"));
+        assert!(!zome.has_function("fn_d"));
+    }
+
+    #[test]
+    fn can_check_function_capabilities() {
+        let mut zome = Zome::new();
+        for &(name, ref membrane) in &[
+            ("public", capabilities::Membrane::Public),
+            ("private", capabilities::Membrane::Agent),
+        ] {
+            let mut capability = capabilities::Capability::new();
+            capability.name = name.to_string();
+            capability.capability.membrane = membrane.clone();
+            for fn_name in &["shared_fn", &format!("{}_fn", name)] {
+                let mut fn_declaration = capabilities::FnDeclaration::new();
+                fn_declaration.name = fn_name.to_string();
+                capability.fn_declarations.push(fn_declaration);
+            }
+            zome.capabilities.push(capability);
+        }
+
+        let names = |fn_name| {
+            zome.capabilities_for(fn_name)
+                .iter()
+                .map(|capability| capability.name.clone())
+                .collect::<Vec<String>>()
+        };
+        assert_eq!(names("shared_fn"), vec!["public", "private"]);
+        assert_eq!(names("private_fn"), vec!["private"]);
+        assert!(names("bogus_fn").is_empty());
+
+        assert!(zome.is_public("shared_fn"));
+        assert!(zome.is_public("public_fn"));
+        assert!(!zome.is_public("private_fn"));
+        assert!(!zome.is_public("bogus_fn"));
+    }
+
+    #[test]
+    fn can_find_function_code() {
+        let mut zome = ZomeBuilder::new()
+            .with_function("without_code", "shared_fn")
+            .with_function("with_code", "shared_fn")
+            .with_function("with_code", "other_fn")
+            .build();
+        assert_eq!(zome.code_for("shared_fn"), None);
+
+        zome.capabilities[1].code.code = vec![0, 1, 2, 3];
+        assert_eq!(
+            zome.code_for("shared_fn"),
+            Some(&zome.capabilities[1].code)
+        );
+        assert_eq!(
+            zome.code_for("other_fn"),
+            Some(&zome.capabilities[1].code)
+        );
+        assert_eq!(zome.code_for("bogus_fn"), None);
+    }
 }
@@ -7,6 +7,7 @@ extern crate hc_dna;
 
 use hc_agent::Agent;
 use hc_core::context::Context;
+use hc_core::entry_store::SimpleEntryStore;
 use hc_core::logger::SimpleLogger;
 use hc_core::persister::SimplePersister;
 use hc_core_api::*;
@@ -33,13 +34,18 @@ fn main() {
         usage();
     }
 
-    //let dna = hc_dna::from_package_file("mydna.hcpkg");
-    let dna = Dna::new();
+    //let dna = hc_dna::from_package_file(Path::new("mydna.hcpkg")).unwrap();
+    let mut dna = Dna::new();
+    dna.name = "hc_test_bin".to_string();
     let agent = Agent::from_string(identity);
     let context = Context {
         agent,
-        logger: Arc::new(Mutex::new(SimpleLogger {})),
+        logger: Arc::new(Mutex::new(SimpleLogger::new())),
         persister: Arc::new(Mutex::new(SimplePersister::new())),
+        entry_store: Arc::new(Mutex::new(SimpleEntryStore::new())),
+        dht: None,
+        instance_name: None,
+        genesis: None,
     };
     let mut hc = Holochain::new(dna, Arc::new(context)).unwrap();
     println!("Created a new instance with identity: {}", identity);
@@ -49,7 +55,7 @@ fn main() {
     println!("Started the app..");
 
     // call a function in the app
-    //hc.call("some_fn");
+    //hc.call("some_fn", "");
 
     // get the state
     {